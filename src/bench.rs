@@ -0,0 +1,116 @@
+use ark_bn254::Fq12;
+use num_bigint::BigUint;
+use plonky2::{
+    field::extension::Extendable,
+    hash::hash_types::RichField,
+    iop::{
+        target::BoolTarget,
+        witness::{PartialWitness, WitnessWrite},
+    },
+    plonk::{
+        circuit_builder::CircuitBuilder,
+        circuit_data::{CircuitConfig, CircuitData},
+        config::GenericConfig,
+    },
+};
+
+use crate::fields::fq12_target::Fq12Target;
+
+// a circuit doing nothing but x^exp for a witnessed x and exp, so proving time measures
+// the exponentiation alone. The result is the only public input
+pub struct Fq12PowCircuit<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+> {
+    pub data: CircuitData<F, C, D>,
+    pub x: Fq12Target<F, D>,
+    pub exp_bits: Vec<BoolTarget>,
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    Fq12PowCircuit<F, C, D>
+{
+    pub fn witness(&self, x: &Fq12, exp: &BigUint) -> PartialWitness<F> {
+        assert!(
+            exp.bits() as usize <= self.exp_bits.len(),
+            "exponent does not fit in {} bits",
+            self.exp_bits.len()
+        );
+        let mut pw = PartialWitness::new();
+        self.x.set_witness(&mut pw, x);
+        for (i, bit) in self.exp_bits.iter().enumerate() {
+            pw.set_bool_target(*bit, exp.bit(i as u64));
+        }
+        pw
+    }
+}
+
+// x^exp with pow_constrained over exp_bit_len little-endian exponent bits
+pub fn build_fq12_pow_circuit<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    config: CircuitConfig,
+    exp_bit_len: usize,
+) -> Fq12PowCircuit<F, C, D> {
+    let mut builder = CircuitBuilder::<F, D>::new(config);
+    let x = Fq12Target::empty(&mut builder);
+    let exp_bits = (0..exp_bit_len)
+        .map(|_| builder.add_virtual_bool_target_safe())
+        .collect::<Vec<_>>();
+    let output = x.pow_constrained(&mut builder, &exp_bits);
+    output.register_public_inputs(&mut builder);
+    Fq12PowCircuit {
+        data: builder.build::<C>(),
+        x,
+        exp_bits,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bn254::Fq12;
+    use ark_ff::Field;
+    use ark_std::UniformRand;
+    use num::bigint::RandBigInt;
+    use plonky2::{
+        field::goldilocks_field::GoldilocksField,
+        plonk::{circuit_data::CircuitConfig, config::PoseidonGoldilocksConfig},
+    };
+
+    use crate::fields::fq12_target::Fq12Target;
+
+    use super::build_fq12_pow_circuit;
+
+    type F = GoldilocksField;
+    type C = PoseidonGoldilocksConfig;
+    const D: usize = 2;
+
+    fn check_fq12_pow_circuit(exp_bit_len: usize) {
+        let rng = &mut rand::thread_rng();
+        let x = Fq12::rand(rng);
+        let exp = rng.gen_biguint(exp_bit_len as u64);
+
+        let circuit =
+            build_fq12_pow_circuit::<F, C, D>(CircuitConfig::standard_ecc_config(), exp_bit_len);
+        let pw = circuit.witness(&x, &exp);
+        let proof = circuit.data.prove(pw).unwrap();
+        assert_eq!(
+            Fq12Target::<F, D>::value_from_public_inputs(&proof.public_inputs),
+            x.pow(exp.to_u64_digits())
+        );
+        circuit.data.verify(proof).unwrap();
+    }
+
+    #[test]
+    fn test_fq12_pow_circuit_64_bits() {
+        check_fq12_pow_circuit(64);
+    }
+
+    #[test]
+    fn test_fq12_pow_circuit_254_bits() {
+        check_fq12_pow_circuit(254);
+    }
+}
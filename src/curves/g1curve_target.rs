@@ -1,4 +1,5 @@
 use ark_bn254::{Fq, G1Affine};
+use ark_ec::AffineRepr;
 use ark_std::UniformRand;
 use itertools::Itertools;
 use plonky2::{
@@ -15,21 +16,27 @@ use rand::SeedableRng;
 
 use crate::fields::{fq_target::FqTarget, fr_target::FrTarget};
 
+// an affine point with an explicit infinity flag, as arkworks' Affine. The identity has the
+// flag set and placeholder coordinates (0, 0); no point of the curve has x = 0 (3 is not a
+// square in Fq), so the flag and the coordinates agree. assert_on_curve ties the flag of a
+// witness point to its coordinates
 #[derive(Clone, Debug)]
 pub struct G1Target<F: RichField + Extendable<D>, const D: usize> {
     pub x: FqTarget<F, D>,
     pub y: FqTarget<F, D>,
+    pub infinity: BoolTarget,
 }
 
 impl<F: RichField + Extendable<D>, const D: usize> G1Target<F, D> {
     pub fn empty(builder: &mut CircuitBuilder<F, D>) -> Self {
         let x = FqTarget::empty(builder);
         let y = FqTarget::empty(builder);
-        G1Target { x, y }
+        let infinity = builder.add_virtual_bool_target_safe();
+        G1Target { x, y, infinity }
     }
 
-    pub fn new(x: FqTarget<F, D>, y: FqTarget<F, D>) -> Self {
-        G1Target { x, y }
+    pub fn new(x: FqTarget<F, D>, y: FqTarget<F, D>, infinity: BoolTarget) -> Self {
+        G1Target { x, y, infinity }
     }
 
     pub fn constant(builder: &mut CircuitBuilder<F, D>, a: G1Affine) -> Self {
@@ -38,10 +45,12 @@ impl<F: RichField + Extendable<D>, const D: usize> G1Target<F, D> {
 
         let x_target = FqTarget::constant(builder, x);
         let y_target = FqTarget::constant(builder, y);
+        let infinity = builder.constant_bool(a.infinity);
 
         G1Target {
             x: x_target,
             y: y_target,
+            infinity,
         }
     }
 
@@ -49,18 +58,56 @@ impl<F: RichField + Extendable<D>, const D: usize> G1Target<F, D> {
         use ark_ff::Field;
         let x = FqTarget::constant(builder, Fq::ZERO);
         let y = FqTarget::constant(builder, Fq::ZERO);
-        G1Target { x, y }
+        let infinity = builder._true();
+        G1Target { x, y, infinity }
+    }
+
+    // the identity, used to seed scalar-mul / MSM accumulators: the flag set, (0, 0) as
+    // coordinates
+    pub fn infinity(builder: &mut CircuitBuilder<F, D>) -> Self {
+        Self::zero(builder)
     }
 
     pub fn connect(builder: &mut CircuitBuilder<F, D>, lhs: &Self, rhs: &Self) {
         FqTarget::connect(builder, &lhs.x, &rhs.x);
         FqTarget::connect(builder, &lhs.y, &rhs.y);
+        builder.connect(lhs.infinity.target, rhs.infinity.target);
     }
 
+    pub fn is_equal(&self, builder: &mut CircuitBuilder<F, D>, rhs: &Self) -> BoolTarget {
+        let x_equal = self.x.is_equal(builder, &rhs.x);
+        let y_equal = self.y.is_equal(builder, &rhs.y);
+        let infinity_equal = builder.is_equal(self.infinity.target, rhs.infinity.target);
+        let equal = builder.and(x_equal, y_equal);
+        builder.and(equal, infinity_equal)
+    }
+
+    pub fn select(
+        builder: &mut CircuitBuilder<F, D>,
+        lhs: &Self,
+        rhs: &Self,
+        flag: &BoolTarget,
+    ) -> Self {
+        let x = FqTarget::select(builder, &lhs.x, &rhs.x, flag);
+        let y = FqTarget::select(builder, &lhs.y, &rhs.y, flag);
+        // a select of two booleans is boolean
+        let infinity = BoolTarget::new_unsafe(builder.select(
+            *flag,
+            lhs.infinity.target,
+            rhs.infinity.target,
+        ));
+        G1Target { x, y, infinity }
+    }
+
+    // keeps the flag, the y of the identity may come out as p instead of 0
     pub fn neg(&self, builder: &mut CircuitBuilder<F, D>) -> Self {
         let x = self.x.clone();
         let y = self.y.neg(builder);
-        G1Target { x, y }
+        G1Target {
+            x,
+            y,
+            infinity: self.infinity,
+        }
     }
 
     pub fn double(&self, builder: &mut CircuitBuilder<F, D>) -> Self {
@@ -80,7 +127,12 @@ impl<F: RichField + Extendable<D>, const D: usize> G1Target<F, D> {
         let lambda_x_diff = lambda.mul(builder, &x_diff);
         let y3 = lambda_x_diff.sub(builder, &y);
 
-        G1Target { x: x3, y: y3 }
+        // self must not be the identity, so neither is the result
+        G1Target {
+            x: x3,
+            y: y3,
+            infinity: self.infinity,
+        }
     }
 
     /// It assumes at least one of the two points is non zero.
@@ -101,8 +153,8 @@ impl<F: RichField + Extendable<D>, const D: usize> G1Target<F, D> {
         let prod = s.mul(builder, &x_diff);
         let y3 = prod.sub(builder, &y1);
 
-        let x1zero = x1.is_zero(builder);
-        let x2zero = x2.is_zero(builder);
+        let x1zero = self.infinity;
+        let x2zero = rhs.infinity;
         let x1full = builder.not(x1zero);
         let x2full = builder.not(x2zero);
         let x1zerox2full = builder.and(x1zero, x2full);
@@ -117,13 +169,61 @@ impl<F: RichField + Extendable<D>, const D: usize> G1Target<F, D> {
 
         let and_fq = FqTarget::select(builder, &y2, &y1, &x1zerox2full);
         let maybe_y3 = FqTarget::select(builder, &y3, &and_fq, &no_zero);
+        let infinity = builder.and(x1zero, x2zero);
 
         G1Target {
             x: maybe_x3,
             y: maybe_y3,
+            infinity,
         }
     }
 
+    // exception-free addition, as G2Target::add_complete: P = Q doubles, P = -Q gives the
+    // identity and either side having the infinity flag set is selected by the flags. The
+    // slope denominator is replaced by one whenever it is zero so that the inverse
+    // generator never sees zero
+    pub fn add_complete(&self, builder: &mut CircuitBuilder<F, D>, rhs: &Self) -> Self {
+        let (x1, y1) = (&self.x, &self.y);
+        let (x2, y2) = (&rhs.x, &rhs.y);
+
+        // differences and sums may come out as p instead of 0, hence reduce
+        let x_diff = x2.sub(builder, x1);
+        let x_equal = x_diff.reduce(builder).is_zero(builder);
+        let y_sum = y1.add(builder, y2);
+        let y_sum_zero = y_sum.reduce(builder).is_zero(builder);
+        let is_identity = builder.and(x_equal, y_sum_zero);
+
+        let y_diff = y2.sub(builder, y1);
+        let x1_squared = x1.mul(builder, x1);
+        let x1_squared_double = x1_squared.add(builder, &x1_squared);
+        let x1_squared_triple = x1_squared_double.add(builder, &x1_squared);
+        let y1_double = y1.add(builder, y1);
+        let num = FqTarget::select(builder, &x1_squared_triple, &y_diff, &x_equal);
+        let den = FqTarget::select(builder, &y1_double, &x_diff, &x_equal);
+        let one = FqTarget::constant(builder, Fq::from(1));
+        let den = FqTarget::select(builder, &one, &den, &is_identity);
+        let den_inv = den.inv(builder);
+        let lambda = num.mul(builder, &den_inv);
+
+        let lambda_squared = lambda.mul(builder, &lambda);
+        let x_sum = x1.add(builder, x2);
+        let x3 = lambda_squared.sub(builder, &x_sum);
+        let x1_minus_x3 = x1.sub(builder, &x3);
+        let lambda_x1_minus_x3 = lambda.mul(builder, &x1_minus_x3);
+        let y3 = lambda_x1_minus_x3.sub(builder, y1);
+        let infinity = builder._false();
+        let sum = G1Target {
+            x: x3,
+            y: y3,
+            infinity,
+        };
+
+        let identity = Self::infinity(builder);
+        let sum = Self::select(builder, &identity, &sum, &is_identity);
+        let sum = Self::select(builder, self, &sum, &rhs.infinity);
+        Self::select(builder, rhs, &sum, &self.infinity)
+    }
+
     pub fn conditional_add(
         &self,
         builder: &mut CircuitBuilder<F, D>,
@@ -131,11 +231,7 @@ impl<F: RichField + Extendable<D>, const D: usize> G1Target<F, D> {
         b: &BoolTarget,
     ) -> Self {
         let sum = self.add(builder, p);
-
-        let x = FqTarget::select(builder, &sum.x, &self.x, b);
-        let y = FqTarget::select(builder, &sum.y, &self.y, b);
-
-        Self { x, y }
+        Self::select(builder, &sum, self, b)
     }
 
     pub fn pow_var_simple(&self, builder: &mut CircuitBuilder<F, D>, s: &FrTarget<F, D>) -> Self {
@@ -165,27 +261,238 @@ impl<F: RichField + Extendable<D>, const D: usize> G1Target<F, D> {
 
         r
     }
+
+    // [scalar] self for little-endian scalar bits, by double-and-add with add_complete so
+    // that no partial sum can hit an exceptional case. The identity as base is swapped for
+    // the generator before doubling (double divides by 2y) and restored at the end, and an
+    // all-zero scalar leaves the accumulator at the identity
+    pub fn scalar_mul(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        scalar_bits: &[BoolTarget],
+    ) -> Self {
+        let is_infinity = self.infinity;
+        let generator = Self::constant(builder, G1Affine::generator());
+        let base = Self::select(builder, &generator, self, &is_infinity);
+
+        let mut acc = Self::infinity(builder);
+        let mut power = base;
+        for (i, bit) in scalar_bits.iter().enumerate() {
+            let sum = acc.add_complete(builder, &power);
+            acc = Self::select(builder, &sum, &acc, bit);
+            if i + 1 < scalar_bits.len() {
+                power = power.double(builder);
+            }
+        }
+
+        let infinity = Self::infinity(builder);
+        Self::select(builder, &infinity, &acc, &is_infinity)
+    }
+
+    // sum_i [scalars[i]] points[i] for little-endian scalar bits (Straus): one most
+    // significant bit first ladder shared by all points, so the doublings are paid once
+    // instead of once per point. add_complete makes identity points and colliding partial
+    // sums safe; scalars may differ in length and no points give the identity
+    pub fn msm(
+        builder: &mut CircuitBuilder<F, D>,
+        scalars: &[Vec<BoolTarget>],
+        points: &[Self],
+    ) -> Self {
+        assert_eq!(scalars.len(), points.len());
+        let num_bits = scalars.iter().map(|bits| bits.len()).max().unwrap_or(0);
+
+        let mut acc = Self::infinity(builder);
+        for j in (0..num_bits).rev() {
+            if j + 1 < num_bits {
+                acc = acc.add_complete(builder, &acc);
+            }
+            for (bits, point) in scalars.iter().zip(points) {
+                if let Some(bit) = bits.get(j) {
+                    let sum = acc.add_complete(builder, point);
+                    acc = Self::select(builder, &sum, &acc, bit);
+                }
+            }
+        }
+        acc
+    }
+
+    // proves result = [scalar] base, e.g. for correct-exponentiation proofs
+    pub fn assert_is_scalar_mul(
+        builder: &mut CircuitBuilder<F, D>,
+        base: &Self,
+        scalar: &FrTarget<F, D>,
+        result: &Self,
+    ) {
+        let expected = base.pow_var_simple(builder, scalar);
+        Self::connect(builder, &expected, result);
+    }
+
+    // decompresses (x, sign) with the flag of assert_sign: y is the root of x^3 + 3 that is
+    // larger than -y iff sign is set. Proving fails if x^3 + 3 is not a square, i.e. x is
+    // not the abscissa of a point. y = 0 cannot occur as G1 has odd order
+    pub fn from_x_and_sign(
+        builder: &mut CircuitBuilder<F, D>,
+        x: &FqTarget<F, D>,
+        y_sign: &BoolTarget,
+    ) -> Self {
+        let x_squared = x.mul(builder, x);
+        let x_cubed = x_squared.mul(builder, x);
+        let b = FqTarget::constant(builder, Fq::from(3));
+        let rhs = x_cubed.add(builder, &b);
+        // y_0^2 = x^3 + 3 is constrained in sqrt_with_sgn, reduce pins the limbs below p so
+        // that the sign comparison sees the canonical value
+        let sgn = builder._false();
+        let y0 = rhs.sqrt_with_sgn(builder, sgn).reduce(builder);
+        let neg_y0 = y0.neg(builder);
+
+        // take -y_0 when y_0 already has the sign the flag rules out
+        let y0_canonical = y0.is_canonical_sign(builder);
+        let sum = builder.add(y0_canonical.target, y_sign.target);
+        let differ = builder.arithmetic(-F::TWO, F::ONE, y0_canonical.target, y_sign.target, sum);
+        let differ = BoolTarget::new_unsafe(differ);
+        let y = FqTarget::select(builder, &y0, &neg_y0, &differ);
+        let infinity = builder._false();
+        G1Target {
+            x: x.clone(),
+            y,
+            infinity,
+        }
+    }
+
+    // compressed public input form: the 8 limbs of x reduced below p, then the parity of
+    // the canonical y. 9 targets instead of 16; the identity has no encoding
+    pub fn register_compressed(&self, builder: &mut CircuitBuilder<F, D>) -> Vec<Target> {
+        let x = self.x.reduce(builder);
+        let parity = self.y.reduce(builder).sgn0(builder);
+        let mut compressed = x.to_limbs(builder).to_vec();
+        compressed.push(parity.target);
+        builder.register_public_inputs(&compressed);
+        compressed
+    }
+
+    // inverse of register_compressed. y^2 = x^3 + 3 is enforced by the square root, so the
+    // point is on the curve. The root is reduced before its parity is read: y and y + p
+    // have opposite parities, so the sign bit of an unreduced root would be free
+    pub fn from_compressed(builder: &mut CircuitBuilder<F, D>, input: &[Target]) -> Self {
+        assert_eq!(input.len(), FqTarget::<F, D>::NUM_LIMBS + 1);
+        let x = FqTarget::from_vec(builder, &input[..FqTarget::<F, D>::NUM_LIMBS]);
+        // x + p also fits in the limbs and decodes to the same point, so the encoding must be
+        // canonical for it to be unique
+        let reduced_x = x.reduce(builder);
+        FqTarget::connect(builder, &x, &reduced_x);
+        let parity = input[FqTarget::<F, D>::NUM_LIMBS];
+        builder.assert_bool(BoolTarget::new_unsafe(parity));
+
+        let x_squared = x.mul(builder, &x);
+        let x_cubed = x_squared.mul(builder, &x);
+        let b = FqTarget::constant(builder, Fq::from(3));
+        let rhs = x_cubed.add(builder, &b);
+        let sgn = builder._false();
+        let y0 = rhs.sqrt_with_sgn(builder, sgn).reduce(builder);
+        let neg_y0 = y0.neg(builder);
+        // y0 != 0, so -y0 = p - y0 is canonical and of the other parity
+        let y0_parity = y0.sgn0(builder);
+        let matches = builder.is_equal(y0_parity.target, parity);
+        let y = FqTarget::select(builder, &y0, &neg_y0, &matches);
+        let infinity = builder._false();
+        G1Target { x, y, infinity }
+    }
+
+    // y^2 = x^3 + 3, or (0, 0) with the infinity flag set. (0, 0) is off the curve, so the
+    // flag is set iff the coordinates are (0, 0)
+    pub fn assert_on_curve(&self, builder: &mut CircuitBuilder<F, D>) {
+        let y_squared = self.y.mul(builder, &self.y);
+        let x_squared = self.x.mul(builder, &self.x);
+        let x_cubed = x_squared.mul(builder, &self.x);
+        let b = FqTarget::constant(builder, Fq::from(3));
+        let rhs = x_cubed.add(builder, &b);
+        let rhs = FqTarget::select(builder, &y_squared, &rhs, &self.infinity);
+        FqTarget::connect(builder, &y_squared, &rhs);
+        for limb in self.x.to_vec().into_iter().chain(self.y.to_vec()) {
+            let masked = builder.mul(limb, self.infinity.target);
+            builder.assert_zero(masked);
+        }
+    }
+
+    // G1 has cofactor 1: every point of the curve has order r, so the subgroup check is the
+    // curve equation
+    pub fn assert_in_subgroup(&self, builder: &mut CircuitBuilder<F, D>) {
+        self.assert_on_curve(builder);
+    }
+
+    // asserts sign is the compression flag of self: set iff y > (p - 1) / 2, i.e. y is the
+    // larger of y, -y as in arkworks' compressed encoding. y is reduced first: y + p also
+    // fits in the limbs and compares as larger, which would let the prover flip the sign
+    pub fn assert_sign(&self, builder: &mut CircuitBuilder<F, D>, sign: &BoolTarget) {
+        let is_canonical = self.y.reduce(builder).is_canonical_sign(builder);
+        let not_sign = builder.not(*sign);
+        builder.connect(is_canonical.target, not_sign.target);
+    }
 }
 
 impl<F: RichField + Extendable<D>, const D: usize> G1Target<F, D> {
+    // x, y, then the infinity flag
     pub fn to_vec(&self) -> Vec<Target> {
-        self.x.to_vec().into_iter().chain(self.y.to_vec()).collect()
+        self.x
+            .to_vec()
+            .into_iter()
+            .chain(self.y.to_vec())
+            .chain([self.infinity.target])
+            .collect()
     }
 
     pub fn from_vec(builder: &mut CircuitBuilder<F, D>, input: &[Target]) -> Self {
-        assert_eq!(input.len(), 16);
+        assert_eq!(input.len(), 17);
         let mut input = input.to_vec();
         let x_raw = input.drain(0..8).collect_vec();
-        let y_raw = input;
+        let y_raw = input.drain(0..8).collect_vec();
+        let infinity = BoolTarget::new_unsafe(input[0]);
+        builder.assert_bool(infinity);
         Self {
             x: FqTarget::from_vec(builder, &x_raw),
             y: FqTarget::from_vec(builder, &y_raw),
+            infinity,
         }
     }
 
     pub fn set_witness<W: WitnessWrite<F>>(&self, pw: &mut W, value: &G1Affine) {
         self.x.set_witness(pw, &value.x);
         self.y.set_witness(pw, &value.y);
+        pw.set_bool_target(self.infinity, value.infinity);
+    }
+}
+
+// G1 point in homogeneous projective coordinates, x = X / Z and y = Y / Z, as produced
+// by inversion-free scalar-mul / MSM gadgets. Pairing can consume it without normalizing
+#[derive(Clone, Debug)]
+pub struct G1ProjectiveTarget<F: RichField + Extendable<D>, const D: usize> {
+    pub x: FqTarget<F, D>,
+    pub y: FqTarget<F, D>,
+    pub z: FqTarget<F, D>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> G1ProjectiveTarget<F, D> {
+    pub fn new(x: FqTarget<F, D>, y: FqTarget<F, D>, z: FqTarget<F, D>) -> Self {
+        G1ProjectiveTarget { x, y, z }
+    }
+
+    pub fn from_affine(builder: &mut CircuitBuilder<F, D>, p: &G1Target<F, D>) -> Self {
+        use ark_ff::Field;
+        let z = FqTarget::constant(builder, Fq::ONE);
+        G1ProjectiveTarget {
+            x: p.x.clone(),
+            y: p.y.clone(),
+            z,
+        }
+    }
+
+    // costs one inversion. z must be nonzero
+    pub fn to_affine(&self, builder: &mut CircuitBuilder<F, D>) -> G1Target<F, D> {
+        let z_inv = self.z.inv(builder);
+        let x = self.x.mul(builder, &z_inv);
+        let y = self.y.mul(builder, &z_inv);
+        let infinity = builder._false();
+        G1Target { x, y, infinity }
     }
 }
 
@@ -193,11 +500,13 @@ impl<F: RichField + Extendable<D>, const D: usize> G1Target<F, D> {
 mod tests {
     use std::marker::PhantomData;
 
-    use ark_bn254::{Fr, G1Affine};
+    use ark_bn254::{Fq, Fr, G1Affine, G1Projective};
     use ark_ec::AffineRepr;
+    use ark_ff::{Field, PrimeField};
     use ark_std::UniformRand;
+    use num_bigint::BigUint;
     use plonky2::{
-        field::goldilocks_field::GoldilocksField,
+        field::{goldilocks_field::GoldilocksField, types::Field as Plonky2Field},
         iop::witness::{PartialWitness, WitnessWrite},
         plonk::{
             circuit_builder::CircuitBuilder, circuit_data::CircuitConfig,
@@ -209,7 +518,7 @@ mod tests {
 
     use crate::{
         curves::{init_logging, BN254GateSerializer, BN254GeneratorSerializer},
-        fields::fr_target::FrTarget,
+        fields::{fq_target::FqTarget, fr_target::FrTarget},
     };
 
     use super::G1Target;
@@ -418,4 +727,406 @@ mod tests {
         let data = builder.build::<C>();
         let _proof = data.prove(pw);
     }
+
+    fn check_add_complete(a: G1Affine, b: G1Affine) {
+        let c_expected: G1Affine = (a + b).into();
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let a_t = G1Target::constant(&mut builder, a);
+        let b_t = G1Target::constant(&mut builder, b);
+        let c_t = a_t.add_complete(&mut builder, &b_t);
+        let c_expected_t = G1Target::constant(&mut builder, c_expected);
+        G1Target::connect(&mut builder, &c_expected_t, &c_t);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_g1_is_infinity() {
+        let rng = &mut rand::thread_rng();
+        let p = G1Affine::rand(rng);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let p_t = G1Target::empty(&mut builder);
+        let neg_p_t = p_t.neg(&mut builder);
+        let inf_t = G1Target::infinity(&mut builder);
+        let cases = [
+            (p_t.add_complete(&mut builder, &neg_p_t), true),
+            (p_t.add_complete(&mut builder, &inf_t), false),
+            (inf_t.add_complete(&mut builder, &inf_t), true),
+            (p_t.add_complete(&mut builder, &p_t), false),
+        ];
+        for (point_t, expected) in cases {
+            let expected = builder.constant_bool(expected);
+            builder.connect(point_t.infinity.target, expected.target);
+        }
+
+        let mut pw = PartialWitness::new();
+        p_t.set_witness(&mut pw, &p);
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_g1_add_complete() {
+        let rng = &mut rand::thread_rng();
+        let p = G1Affine::rand(rng);
+        let q = G1Affine::rand(rng);
+        let zero = G1Affine::zero();
+        check_add_complete(p, q);
+        check_add_complete(p, p);
+        check_add_complete(p, -p);
+        check_add_complete(zero, p);
+        check_add_complete(p, zero);
+        check_add_complete(zero, zero);
+    }
+
+    fn check_scalar_mul(p: G1Affine, n: Fr) {
+        let expected: G1Affine = (G1Projective::from(p) * n).into();
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let p_t = G1Target::constant(&mut builder, p);
+        let n_t = FrTarget::constant(&mut builder, n);
+        let bits = builder.split_nonnative_to_bits(&n_t.target);
+        let r_t = p_t.scalar_mul(&mut builder, &bits);
+        let expected_t = G1Target::constant(&mut builder, expected);
+        G1Target::connect(&mut builder, &r_t, &expected_t);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_scalar_mul() {
+        let rng = &mut rand::thread_rng();
+        check_scalar_mul(G1Affine::generator(), Fr::rand(rng));
+    }
+
+    #[test]
+    fn test_scalar_mul_edge_cases() {
+        let rng = &mut rand::thread_rng();
+        let p = G1Affine::rand(rng);
+        check_scalar_mul(p, Fr::from(0));
+        check_scalar_mul(p, Fr::from(1));
+        check_scalar_mul(p, -Fr::from(1));
+        check_scalar_mul(G1Affine::zero(), Fr::rand(rng));
+    }
+
+    #[test]
+    fn test_msm() {
+        let rng = &mut rand::thread_rng();
+        let points = (0..4).map(|_| G1Affine::rand(rng)).collect::<Vec<_>>();
+        let scalars = (0..4).map(|_| Fr::rand(rng)).collect::<Vec<_>>();
+        let expected: G1Affine = points
+            .iter()
+            .zip(&scalars)
+            .map(|(p, s)| G1Projective::from(*p) * s)
+            .sum::<G1Projective>()
+            .into();
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let points_t = points
+            .iter()
+            .map(|p| G1Target::constant(&mut builder, *p))
+            .collect::<Vec<_>>();
+        let scalars_t = scalars
+            .iter()
+            .map(|s| {
+                let s_t = FrTarget::constant(&mut builder, *s);
+                builder.split_nonnative_to_bits(&s_t.target)
+            })
+            .collect::<Vec<_>>();
+        let r_t = G1Target::msm(&mut builder, &scalars_t, &points_t);
+        let expected_t = G1Target::constant(&mut builder, expected);
+        G1Target::connect(&mut builder, &r_t, &expected_t);
+        let empty_t = G1Target::msm(&mut builder, &[], &[]);
+        let infinity_t = G1Target::infinity(&mut builder);
+        G1Target::connect(&mut builder, &empty_t, &infinity_t);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_g1_add_infinity() {
+        let rng = &mut rand::thread_rng();
+        let p = G1Affine::rand(rng);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let p_t = G1Target::constant(&mut builder, p);
+        let inf_t = G1Target::infinity(&mut builder);
+        let left_t = inf_t.add(&mut builder, &p_t);
+        let right_t = p_t.add(&mut builder, &inf_t);
+
+        G1Target::connect(&mut builder, &left_t, &p_t);
+        G1Target::connect(&mut builder, &right_t, &p_t);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_assert_is_scalar_mul() {
+        let rng = &mut rand::thread_rng();
+        let p = G1Affine::rand(rng);
+        let k = Fr::rand(rng);
+        let q: G1Affine = (p * k).into();
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let p_t = G1Target::constant(&mut builder, p);
+        let k_t = FrTarget::constant(&mut builder, k);
+        let q_t = G1Target::constant(&mut builder, q);
+        G1Target::assert_is_scalar_mul(&mut builder, &p_t, &k_t, &q_t);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_assert_is_scalar_mul_wrong_result() {
+        let rng = &mut rand::thread_rng();
+        let p = G1Affine::rand(rng);
+        let k = Fr::rand(rng);
+        let wrong: G1Affine = (p * (k + Fr::from(1))).into();
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let p_t = G1Target::constant(&mut builder, p);
+        let k_t = FrTarget::constant(&mut builder, k);
+        let wrong_t = G1Target::constant(&mut builder, wrong);
+        G1Target::assert_is_scalar_mul(&mut builder, &p_t, &k_t, &wrong_t);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw);
+    }
+
+    // y is written into the limbs as y + k p
+    fn check_assert_sign(p: G1Affine, flip: bool, k: u32) {
+        let sign = p.y > -p.y;
+        let modulus: BigUint = Fq::MODULUS.into();
+        let mut y_limbs = (BigUint::from(p.y) + modulus * k).to_u32_digits();
+        y_limbs.resize(FqTarget::<F, D>::NUM_LIMBS, 0);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let p_t = G1Target::empty(&mut builder);
+        let sign_t = builder.add_virtual_bool_target_safe();
+        p_t.assert_sign(&mut builder, &sign_t);
+
+        let mut pw = PartialWitness::new();
+        p_t.x.set_witness(&mut pw, &p.x);
+        pw.set_bool_target(p_t.infinity, false);
+        for (l_t, l) in p_t.y.target.value.limbs.iter().zip(y_limbs) {
+            pw.set_target(l_t.0, F::from_canonical_u32(l));
+        }
+        pw.set_bool_target(sign_t, sign ^ flip);
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    fn check_from_x_and_sign(x: Fq, sign: bool, expected: Option<G1Affine>) {
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = FqTarget::constant(&mut builder, x);
+        let sign_t = builder.constant_bool(sign);
+        let p_t = G1Target::from_x_and_sign(&mut builder, &x_t, &sign_t);
+        if let Some(expected) = expected {
+            let expected_t = G1Target::constant(&mut builder, expected);
+            G1Target::connect(&mut builder, &p_t, &expected_t);
+        }
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_from_x_and_sign() {
+        let rng = &mut rand::thread_rng();
+        let p = G1Affine::rand(rng);
+        let sign = p.y > -p.y;
+        check_from_x_and_sign(p.x, sign, Some(p));
+        check_from_x_and_sign(p.x, !sign, Some(-p));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_from_x_and_sign_rejects_off_curve_x() {
+        let rng = &mut rand::thread_rng();
+        let x = loop {
+            let x = Fq::rand(rng);
+            if (x * x * x + Fq::from(3)).legendre().is_qnr() {
+                break x;
+            }
+        };
+        check_from_x_and_sign(x, false, None);
+    }
+
+    #[test]
+    fn test_compressed_roundtrip() {
+        let rng = &mut rand::thread_rng();
+        let p = G1Affine::rand(rng);
+        let p_y: BigUint = p.y.into();
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let p_t = G1Target::empty(&mut builder);
+        let compressed = p_t.register_compressed(&mut builder);
+        let decoded_t = G1Target::from_compressed(&mut builder, &compressed);
+        G1Target::connect(&mut builder, &decoded_t, &p_t);
+
+        let mut pw = PartialWitness::new();
+        p_t.set_witness(&mut pw, &p);
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        let mut expected: Vec<F> = BigUint::from(p.x)
+            .to_u32_digits()
+            .into_iter()
+            .map(F::from_canonical_u32)
+            .collect();
+        expected.resize(8, F::ZERO);
+        expected.push(F::from_bool(p_y.bit(0)));
+        assert_eq!(proof.public_inputs, expected);
+        data.verify(proof).unwrap();
+    }
+
+    fn check_from_compressed(p: G1Affine, k: u32) {
+        let modulus: BigUint = Fq::MODULUS.into();
+        let mut x_limbs = (BigUint::from(p.x) + modulus * k).to_u32_digits();
+        x_limbs.resize(FqTarget::<F, D>::NUM_LIMBS, 0);
+        let p_y: BigUint = p.y.into();
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let input = builder.add_virtual_targets(FqTarget::<F, D>::NUM_LIMBS + 1);
+        let decoded_t = G1Target::from_compressed(&mut builder, &input);
+        let p_t = G1Target::constant(&mut builder, p);
+        G1Target::connect(&mut builder, &decoded_t, &p_t);
+
+        let mut pw = PartialWitness::new();
+        for (&l_t, l) in input.iter().zip(x_limbs) {
+            pw.set_target(l_t, F::from_canonical_u32(l));
+        }
+        pw.set_target(input[FqTarget::<F, D>::NUM_LIMBS], F::from_bool(p_y.bit(0)));
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_from_compressed() {
+        let rng = &mut rand::thread_rng();
+        check_from_compressed(G1Affine::rand(rng), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_from_compressed_rejects_unreduced_x() {
+        let rng = &mut rand::thread_rng();
+        check_from_compressed(G1Affine::rand(rng), 1);
+    }
+
+    fn check_in_subgroup(p: G1Affine) {
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let p_t = G1Target::constant(&mut builder, p);
+        p_t.assert_on_curve(&mut builder);
+        p_t.assert_in_subgroup(&mut builder);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_assert_on_curve_and_in_subgroup() {
+        check_in_subgroup(G1Affine::rand(&mut rand::thread_rng()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_assert_on_curve_rejects_off_curve_point() {
+        let p = G1Affine::rand(&mut rand::thread_rng());
+        let bad = G1Affine::new_unchecked(p.x, p.y + Fq::ONE);
+        check_in_subgroup(bad);
+    }
+
+    #[test]
+    fn test_assert_on_curve_accepts_flagged_identity() {
+        check_in_subgroup(G1Affine::identity());
+    }
+
+    // the identity's coordinates without the flag
+    #[test]
+    #[should_panic]
+    fn test_assert_on_curve_rejects_unflagged_zero() {
+        check_in_subgroup(G1Affine::new_unchecked(Fq::ZERO, Fq::ZERO));
+    }
+
+    #[test]
+    fn test_to_vec_from_vec_keeps_infinity() {
+        let rng = &mut rand::thread_rng();
+        for p in [G1Affine::rand(rng), G1Affine::identity()] {
+            let config = CircuitConfig::standard_ecc_config();
+            let mut builder = CircuitBuilder::<F, D>::new(config);
+            let p_t = G1Target::empty(&mut builder);
+            let vec = p_t.to_vec();
+            assert_eq!(vec.len(), 17);
+            let q_t = G1Target::from_vec(&mut builder, &vec);
+            let expected_t = G1Target::constant(&mut builder, p);
+            G1Target::connect(&mut builder, &q_t, &expected_t);
+
+            let mut pw = PartialWitness::new();
+            p_t.set_witness(&mut pw, &p);
+            let data = builder.build::<C>();
+            let _proof = data.prove(pw).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_assert_sign() {
+        check_assert_sign(G1Affine::rand(&mut rand::thread_rng()), false, 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_assert_sign_flipped() {
+        check_assert_sign(G1Affine::rand(&mut rand::thread_rng()), true, 0);
+    }
+
+    // a point whose y <= (p - 1) / 2, i.e. with the flag unset
+    fn random_point_with_canonical_y() -> G1Affine {
+        let p = G1Affine::rand(&mut rand::thread_rng());
+        if p.y > -p.y {
+            -p
+        } else {
+            p
+        }
+    }
+
+    #[test]
+    fn test_assert_sign_unreduced_y() {
+        check_assert_sign(random_point_with_canonical_y(), false, 1);
+    }
+
+    // y + p compares as larger than (p - 1) / 2, so without the reduction the set flag
+    // would be accepted for a y that has it unset
+    #[test]
+    #[should_panic]
+    fn test_assert_sign_rejects_flip_through_unreduced_y() {
+        check_assert_sign(random_point_with_canonical_y(), true, 1);
+    }
 }
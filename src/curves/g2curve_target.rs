@@ -1,6 +1,9 @@
-use ark_bn254::G2Affine;
-use ark_ff::UniformRand;
+use ark_bn254::{g2, Config, Fq2, G2Affine};
+use ark_ec::{bn::BnConfig, short_weierstrass::SWCurveConfig, AffineRepr};
+use ark_ff::{Field, UniformRand};
 use itertools::Itertools;
+use num_bigint::BigUint;
+use num_traits::Zero;
 use plonky2::{
     field::extension::Extendable,
     hash::hash_types::RichField,
@@ -13,23 +16,27 @@ use plonky2::{
 use plonky2_ecdsa::gadgets::nonnative::CircuitBuilderNonNative;
 use rand::SeedableRng;
 
-use crate::fields::{fq2_target::Fq2Target, fr_target::FrTarget};
+use crate::fields::{fq2_target::Fq2Target, fq_target::FqTarget, fr_target::FrTarget};
 
+// affine point on the twist with an explicit infinity flag, see G1Target: the identity has
+// the flag set and coordinates (0, 0), which are off the curve as b' != 0
 #[derive(Clone, Debug)]
 pub struct G2Target<F: RichField + Extendable<D>, const D: usize> {
     pub x: Fq2Target<F, D>,
     pub y: Fq2Target<F, D>,
+    pub infinity: BoolTarget,
 }
 
 impl<F: RichField + Extendable<D>, const D: usize> G2Target<F, D> {
     pub fn empty(builder: &mut CircuitBuilder<F, D>) -> Self {
         let x = Fq2Target::empty(builder);
         let y = Fq2Target::empty(builder);
-        G2Target { x, y }
+        let infinity = builder.add_virtual_bool_target_safe();
+        G2Target { x, y, infinity }
     }
 
-    pub fn new(x: Fq2Target<F, D>, y: Fq2Target<F, D>) -> Self {
-        G2Target { x, y }
+    pub fn new(x: Fq2Target<F, D>, y: Fq2Target<F, D>, infinity: BoolTarget) -> Self {
+        G2Target { x, y, infinity }
     }
 
     pub fn constant(builder: &mut CircuitBuilder<F, D>, a: G2Affine) -> Self {
@@ -38,22 +45,81 @@ impl<F: RichField + Extendable<D>, const D: usize> G2Target<F, D> {
 
         let x_target = Fq2Target::constant(builder, x);
         let y_target = Fq2Target::constant(builder, y);
+        let infinity = builder.constant_bool(a.infinity);
 
         G2Target {
             x: x_target,
             y: y_target,
+            infinity,
         }
     }
 
+    pub fn zero(builder: &mut CircuitBuilder<F, D>) -> Self {
+        let x = Fq2Target::constant(builder, Fq2::ZERO);
+        let y = Fq2Target::constant(builder, Fq2::ZERO);
+        let infinity = builder._true();
+        G2Target { x, y, infinity }
+    }
+
+    // the identity, used to seed scalar-mul / MSM accumulators: the flag set, (0, 0) as
+    // coordinates
+    pub fn infinity(builder: &mut CircuitBuilder<F, D>) -> Self {
+        Self::zero(builder)
+    }
+
     pub fn connect(builder: &mut CircuitBuilder<F, D>, lhs: &Self, rhs: &Self) {
         Fq2Target::connect(builder, &lhs.x, &rhs.x);
         Fq2Target::connect(builder, &lhs.y, &rhs.y);
+        builder.connect(lhs.infinity.target, rhs.infinity.target);
+    }
+
+    pub fn is_equal(&self, builder: &mut CircuitBuilder<F, D>, rhs: &Self) -> BoolTarget {
+        let x_equal = self.x.is_equal(builder, &rhs.x);
+        let y_equal = self.y.is_equal(builder, &rhs.y);
+        let infinity_equal = builder.is_equal(self.infinity.target, rhs.infinity.target);
+        let equal = builder.and(x_equal, y_equal);
+        builder.and(equal, infinity_equal)
     }
 
+    pub fn select(
+        builder: &mut CircuitBuilder<F, D>,
+        lhs: &Self,
+        rhs: &Self,
+        flag: &BoolTarget,
+    ) -> Self {
+        let x = Fq2Target::select(builder, &lhs.x, &rhs.x, flag);
+        let y = Fq2Target::select(builder, &lhs.y, &rhs.y, flag);
+        // a select of two booleans is boolean
+        let infinity = BoolTarget::new_unsafe(builder.select(
+            *flag,
+            lhs.infinity.target,
+            rhs.infinity.target,
+        ));
+        G2Target { x, y, infinity }
+    }
+
+    // keeps the flag, the y of the identity may come out as p instead of 0
     pub fn neg(&self, builder: &mut CircuitBuilder<F, D>) -> Self {
         let x = self.x.clone();
         let y = self.y.neg(builder);
-        G2Target { x, y }
+        G2Target {
+            x,
+            y,
+            infinity: self.infinity,
+        }
+    }
+
+    // -self if flag is set, self otherwise. neg alone may write the y of the identity as p
+    // rather than 0, so the identity is kept as is: its negation is the identity
+    pub fn conditional_neg(&self, builder: &mut CircuitBuilder<F, D>, flag: &BoolTarget) -> Self {
+        let neg_y = self.y.neg(builder);
+        let neg_y = Fq2Target::select(builder, &self.y, &neg_y, &self.infinity);
+        let y = Fq2Target::select(builder, &neg_y, &self.y, flag);
+        G2Target {
+            x: self.x.clone(),
+            y,
+            infinity: self.infinity,
+        }
     }
 
     pub fn double(&self, builder: &mut CircuitBuilder<F, D>) -> Self {
@@ -73,9 +139,15 @@ impl<F: RichField + Extendable<D>, const D: usize> G2Target<F, D> {
         let lambda_x_diff = lambda.mul(builder, &x_diff);
         let y3 = lambda_x_diff.sub(builder, &y);
 
-        G2Target { x: x3, y: y3 }
+        // self must not be the identity, so neither is the result
+        G2Target {
+            x: x3,
+            y: y3,
+            infinity: self.infinity,
+        }
     }
 
+    /// It assumes at least one of the two points is non zero.
     pub fn add(&self, builder: &mut CircuitBuilder<F, D>, rhs: &Self) -> Self {
         let x1 = self.x.clone();
         let y1 = self.y.clone();
@@ -93,7 +165,72 @@ impl<F: RichField + Extendable<D>, const D: usize> G2Target<F, D> {
         let prod = s.mul(builder, &x_diff);
         let y3 = prod.sub(builder, &y1);
 
-        G2Target { x: x3, y: y3 }
+        let x1zero = self.infinity;
+        let x2zero = rhs.infinity;
+        let x1full = builder.not(x1zero);
+        let x2full = builder.not(x2zero);
+        let x1zerox2full = builder.and(x1zero, x2full);
+        let no_zero = builder.and(x1full, x2full);
+
+        // same selection as G1Target::add: x2 if only x1 is zero, x1 if only x2 is zero
+        let and_fq2 = Fq2Target::select(builder, &x2, &x1, &x1zerox2full);
+        let maybe_x3 = Fq2Target::select(builder, &x3, &and_fq2, &no_zero);
+
+        let and_fq2 = Fq2Target::select(builder, &y2, &y1, &x1zerox2full);
+        let maybe_y3 = Fq2Target::select(builder, &y3, &and_fq2, &no_zero);
+        let infinity = builder.and(x1zero, x2zero);
+
+        G2Target {
+            x: maybe_x3,
+            y: maybe_y3,
+            infinity,
+        }
+    }
+
+    // exception-free addition: handles P = Q (doubling), P = -Q (the identity) and either
+    // side having the infinity flag set. The chord and tangent slopes share one formula,
+    // lambda = num / den with (num, den) = (y2 - y1, x2 - x1) or (3 x1^2, 2 y1) if x1 = x2.
+    // den is zero only when the sum is the identity, and is then replaced by one so that
+    // the inverse generator never sees zero
+    pub fn add_complete(&self, builder: &mut CircuitBuilder<F, D>, rhs: &Self) -> Self {
+        let (x1, y1) = (&self.x, &self.y);
+        let (x2, y2) = (&rhs.x, &rhs.y);
+
+        let x_diff = x2.sub(builder, x1);
+        let x_equal = x_diff.is_zero(builder);
+        let y_sum = y1.add(builder, y2);
+        let y_sum_zero = y_sum.is_zero(builder);
+        let is_identity = builder.and(x_equal, y_sum_zero);
+
+        let y_diff = y2.sub(builder, y1);
+        let x1_squared = x1.mul(builder, x1);
+        let x1_squared_double = x1_squared.add(builder, &x1_squared);
+        let x1_squared_triple = x1_squared_double.add(builder, &x1_squared);
+        let y1_double = y1.add(builder, y1);
+        let num = Fq2Target::select(builder, &x1_squared_triple, &y_diff, &x_equal);
+        let den = Fq2Target::select(builder, &y1_double, &x_diff, &x_equal);
+        let one = Fq2Target::constant(builder, Fq2::ONE);
+        let den = Fq2Target::select(builder, &one, &den, &is_identity);
+        let den_inv = den.inv(builder);
+        let lambda = num.mul(builder, &den_inv);
+
+        let lambda_squared = lambda.mul(builder, &lambda);
+        let x_sum = x1.add(builder, x2);
+        let x3 = lambda_squared.sub(builder, &x_sum);
+        let x1_minus_x3 = x1.sub(builder, &x3);
+        let lambda_x1_minus_x3 = lambda.mul(builder, &x1_minus_x3);
+        let y3 = lambda_x1_minus_x3.sub(builder, y1);
+        let infinity = builder._false();
+        let sum = G2Target {
+            x: x3,
+            y: y3,
+            infinity,
+        };
+
+        let identity = Self::infinity(builder);
+        let sum = Self::select(builder, &identity, &sum, &is_identity);
+        let sum = Self::select(builder, self, &sum, &rhs.infinity);
+        Self::select(builder, rhs, &sum, &self.infinity)
     }
 
     pub fn conditional_add(
@@ -103,16 +240,16 @@ impl<F: RichField + Extendable<D>, const D: usize> G2Target<F, D> {
         b: &BoolTarget,
     ) -> Self {
         let sum = self.add(builder, p);
-
-        let x = Fq2Target::select(builder, &sum.x, &self.x, b);
-        let y = Fq2Target::select(builder, &sum.y, &self.y, b);
-
-        Self { x, y }
+        Self::select(builder, &sum, self, b)
     }
 
     pub fn pow_var_simple(&self, builder: &mut CircuitBuilder<F, D>, s: &FrTarget<F, D>) -> Self {
         let bits = builder.split_nonnative_to_bits(&s.target);
+        self.pow_var_bits(builder, &bits)
+    }
 
+    // little-endian bits, the scalar must be nonzero
+    pub fn pow_var_bits(&self, builder: &mut CircuitBuilder<F, D>, bits: &[BoolTarget]) -> Self {
         let mut doubles = vec![];
         let mut v = self.clone();
         doubles.push(v.clone());
@@ -137,36 +274,156 @@ impl<F: RichField + Extendable<D>, const D: usize> G2Target<F, D> {
 
         r
     }
+
+    // double-and-add over a constant scalar. Assumes self is in G2 and 1 < k < r, so no
+    // intermediate sum hits the exceptional cases of add
+    pub fn mul_const_scalar(&self, builder: &mut CircuitBuilder<F, D>, k: &BigUint) -> Self {
+        assert!(!k.is_zero());
+        let mut r = self.clone();
+        for i in (0..k.bits() - 1).rev() {
+            r = r.double(builder);
+            if k.bit(i) {
+                r = r.add(builder, self);
+            }
+        }
+        r
+    }
+
+    // the untwist-Frobenius-twist endomorphism,
+    // (x, y) -> (conj(x) * TWIST_MUL_BY_Q_X, conj(y) * TWIST_MUL_BY_Q_Y)
+    pub fn psi(&self, builder: &mut CircuitBuilder<F, D>) -> Self {
+        let x = self
+            .x
+            .conjugate(builder)
+            .mul_const(builder, &Config::TWIST_MUL_BY_Q_X);
+        let y = self
+            .y
+            .conjugate(builder)
+            .mul_const(builder, &Config::TWIST_MUL_BY_Q_Y);
+        G2Target {
+            x,
+            y,
+            infinity: self.infinity,
+        }
+    }
+
+    // psi^power. Two conjugations cancel, so psi^2 is (x * N(TWIST_MUL_BY_Q_X),
+    // y * N(TWIST_MUL_BY_Q_Y)) with N the Fq2 -> Fq norm: an even power costs Fq constant
+    // muls only and an odd power one psi more. On G2, psi^power is [p^power]
+    pub fn frobenius(&self, builder: &mut CircuitBuilder<F, D>, power: usize) -> Self {
+        let k = [(power / 2) as u64];
+        let x_coeff = Config::TWIST_MUL_BY_Q_X.norm().pow(k);
+        let y_coeff = Config::TWIST_MUL_BY_Q_Y.norm().pow(k);
+        let x = self.x.mul_scalar_const(builder, &x_coeff);
+        let y = self.y.mul_scalar_const(builder, &y_coeff);
+        let res = G2Target {
+            x,
+            y,
+            infinity: self.infinity,
+        };
+        if power % 2 == 1 {
+            res.psi(builder)
+        } else {
+            res
+        }
+    }
+
+    // y^2 = x^3 + b' on the twist, b' = 3 / (9 + u), or (0, 0) with the infinity flag set.
+    // (0, 0) is off the curve, so the flag is set iff the coordinates are (0, 0)
+    pub fn assert_on_curve(&self, builder: &mut CircuitBuilder<F, D>) {
+        let y_squared = self.y.mul(builder, &self.y);
+        let x_squared = self.x.mul(builder, &self.x);
+        let x_cubed = x_squared.mul(builder, &self.x);
+        let b = Fq2Target::constant(builder, <g2::Config as SWCurveConfig>::COEFF_B);
+        let rhs = x_cubed.add(builder, &b);
+        let rhs = Fq2Target::select(builder, &y_squared, &rhs, &self.infinity);
+        Fq2Target::connect(builder, &y_squared, &rhs);
+        for limb in self.x.to_vec().into_iter().chain(self.y.to_vec()) {
+            let masked = builder.mul(limb, self.infinity.target);
+            builder.assert_zero(masked);
+        }
+    }
+
+    // on G2, psi acts as multiplication by p = 6x^2 mod r, and psi(P) = [6x^2] P holds
+    // only for P in G2 (El Housni, Guillevic, Piellard, "Co-factor clearing and subgroup
+    // membership testing on pairing-friendly curves"). The curve equation is asserted too,
+    // so this is the whole check for an untrusted point
+    pub fn assert_in_subgroup(&self, builder: &mut CircuitBuilder<F, D>) {
+        self.assert_on_curve(builder);
+        // the identity is in G2, but the incomplete adds of mul_const_scalar would divide by
+        // zero on (0, 0): the generator is checked in its place
+        let generator = Self::constant(builder, G2Affine::generator());
+        let p = Self::select(builder, &generator, self, &self.infinity);
+        let psi = p.psi(builder);
+        let six_x_squared = six_x_squared();
+        let expected = p.mul_const_scalar(builder, &six_x_squared);
+        Self::connect(builder, &psi, &expected);
+    }
+
+    // assert_in_subgroup for each point. A random linear fold is no cheaper here: the
+    // cofactor's smallest prime is only 10069, so a sound fold needs several independent
+    // folds of wide coefficients, which costs more than the psi check per point
+    pub fn assert_all_in_subgroup(builder: &mut CircuitBuilder<F, D>, points: &[Self]) {
+        for p in points {
+            p.assert_in_subgroup(builder);
+        }
+    }
+}
+
+fn six_x_squared() -> BigUint {
+    let x = BigUint::from_slice(
+        &Config::X
+            .iter()
+            .flat_map(|limb| [*limb as u32, (*limb >> 32) as u32])
+            .collect_vec(),
+    );
+    BigUint::from(6u32) * &x * &x
 }
 
 impl<F: RichField + Extendable<D>, const D: usize> G2Target<F, D> {
+    // x, y, then the infinity flag
     pub fn to_vec(&self) -> Vec<Target> {
-        self.x.to_vec().into_iter().chain(self.y.to_vec()).collect()
+        self.x
+            .to_vec()
+            .into_iter()
+            .chain(self.y.to_vec())
+            .chain([self.infinity.target])
+            .collect()
     }
 
     pub fn from_vec(builder: &mut CircuitBuilder<F, D>, input: &[Target]) -> Self {
-        let num_lims = 8;
-        let num_fq2_lims = 2 * num_lims;
-        assert_eq!(input.len(), num_fq2_lims * 2);
+        let num_limbs = FqTarget::<F, D>::NUM_LIMBS;
+        let num_fq2_limbs = 2 * num_limbs;
+        assert_eq!(
+            input.len(),
+            num_fq2_limbs * 2 + 1,
+            "G2Target::from_vec expects 4 coefficients of {num_limbs} limbs and a flag"
+        );
         let mut input = input.to_vec();
-        let x_raw = input.drain(0..num_fq2_lims).collect_vec();
-        let y_raw = input;
+        let x_raw = input.drain(0..num_fq2_limbs).collect_vec();
+        let y_raw = input.drain(0..num_fq2_limbs).collect_vec();
+        let infinity = BoolTarget::new_unsafe(input[0]);
+        builder.assert_bool(infinity);
         Self {
             x: Fq2Target::from_vec(builder, &x_raw),
             y: Fq2Target::from_vec(builder, &y_raw),
+            infinity,
         }
     }
 
     pub fn set_witness<W: WitnessWrite<F>>(&self, pw: &mut W, value: &G2Affine) {
         self.x.set_witness(pw, &value.x);
         self.y.set_witness(pw, &value.y);
+        pw.set_bool_target(self.infinity, value.infinity);
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use ark_bn254::{Fr, G2Affine};
-    use ark_std::UniformRand;
+    use ark_bn254::{Fq, Fq2, Fr, G2Affine};
+    use ark_ec::AffineRepr;
+    use ark_ff::{Field, PrimeField};
+    use ark_std::{UniformRand, Zero};
     use plonky2::{
         field::goldilocks_field::GoldilocksField,
         iop::witness::PartialWitness,
@@ -176,7 +433,7 @@ mod tests {
         },
     };
 
-    use crate::fields::fr_target::FrTarget;
+    use crate::{curves::map_to_g2::map_to_g2_without_cofactor_mul, fields::fr_target::FrTarget};
 
     use super::G2Target;
 
@@ -184,6 +441,27 @@ mod tests {
     type C = PoseidonGoldilocksConfig;
     const D: usize = 2;
 
+    #[test]
+    fn test_g2_frobenius() {
+        let rng = &mut rand::thread_rng();
+        let q = G2Affine::rand(rng);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let q_t = G2Target::constant(&mut builder, q);
+        let mut expected = q;
+        for power in 0..4 {
+            let frob_t = q_t.frobenius(&mut builder, power);
+            let expected_t = G2Target::constant(&mut builder, expected);
+            G2Target::connect(&mut builder, &frob_t, &expected_t);
+            expected = expected.mul_bigint(Fq::MODULUS).into();
+        }
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
     #[test]
     fn test_g2_add() {
         let rng = &mut rand::thread_rng();
@@ -243,6 +521,31 @@ mod tests {
         let _proof = data.prove(pw);
     }
 
+    #[test]
+    fn test_g2_conditional_neg() {
+        let rng = &mut rand::thread_rng();
+        let a = G2Affine::rand(rng);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let a_t = G2Target::constant(&mut builder, a);
+        let neg_a_t = G2Target::constant(&mut builder, -a);
+        let inf_t = G2Target::infinity(&mut builder);
+        let yes = builder._true();
+        let no = builder._false();
+
+        let negated_t = a_t.conditional_neg(&mut builder, &yes);
+        G2Target::connect(&mut builder, &negated_t, &neg_a_t);
+        let kept_t = a_t.conditional_neg(&mut builder, &no);
+        G2Target::connect(&mut builder, &kept_t, &a_t);
+        let neg_inf_t = inf_t.conditional_neg(&mut builder, &yes);
+        G2Target::connect(&mut builder, &neg_inf_t, &inf_t);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
     #[test]
     fn test_pow_var_simple_g2() {
         let rng = &mut rand::thread_rng();
@@ -268,4 +571,208 @@ mod tests {
         let data = builder.build::<C>();
         let _proof = data.prove(pw);
     }
+
+    #[test]
+    fn test_g2_add_infinity() {
+        let rng = &mut rand::thread_rng();
+        let p = G2Affine::rand(rng);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let p_t = G2Target::constant(&mut builder, p);
+        let inf_t = G2Target::infinity(&mut builder);
+        let left_t = inf_t.add(&mut builder, &p_t);
+        let right_t = p_t.add(&mut builder, &inf_t);
+
+        G2Target::connect(&mut builder, &left_t, &p_t);
+        G2Target::connect(&mut builder, &right_t, &p_t);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    fn check_add_complete(a: G2Affine, b: G2Affine) {
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let a_t = G2Target::constant(&mut builder, a);
+        let b_t = G2Target::constant(&mut builder, b);
+        let c_t = a_t.add_complete(&mut builder, &b_t);
+        let c_expected_t = if (a + b).is_zero() {
+            G2Target::infinity(&mut builder)
+        } else {
+            G2Target::constant(&mut builder, (a + b).into())
+        };
+        G2Target::connect(&mut builder, &c_expected_t, &c_t);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_g2_add_complete() {
+        let rng = &mut rand::thread_rng();
+        let p = G2Affine::rand(rng);
+        let q = G2Affine::rand(rng);
+        check_add_complete(p, q);
+        check_add_complete(p, p);
+        check_add_complete(p, -p);
+    }
+
+    #[test]
+    fn test_g2_is_infinity() {
+        let rng = &mut rand::thread_rng();
+        let p = G2Affine::rand(rng);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let p_t = G2Target::empty(&mut builder);
+        let neg_p_t = p_t.neg(&mut builder);
+        let inf_t = G2Target::infinity(&mut builder);
+        let cases = [
+            (p_t.add_complete(&mut builder, &neg_p_t), true),
+            (p_t.add_complete(&mut builder, &inf_t), false),
+            (inf_t.add_complete(&mut builder, &inf_t), true),
+            (p_t.add_complete(&mut builder, &p_t), false),
+        ];
+        for (point_t, expected) in cases {
+            let expected = builder.constant_bool(expected);
+            builder.connect(point_t.infinity.target, expected.target);
+        }
+
+        let mut pw = PartialWitness::new();
+        p_t.set_witness(&mut pw, &p);
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_g2_add_complete_infinity() {
+        let rng = &mut rand::thread_rng();
+        let p = G2Affine::rand(rng);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let p_t = G2Target::constant(&mut builder, p);
+        let inf_t = G2Target::infinity(&mut builder);
+        let left_t = inf_t.add_complete(&mut builder, &p_t);
+        let right_t = p_t.add_complete(&mut builder, &inf_t);
+        let both_t = inf_t.add_complete(&mut builder, &inf_t);
+
+        G2Target::connect(&mut builder, &left_t, &p_t);
+        G2Target::connect(&mut builder, &right_t, &p_t);
+        G2Target::connect(&mut builder, &both_t, &inf_t);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_assert_all_in_subgroup() {
+        let rng = &mut rand::thread_rng();
+        let points = (0..3).map(|_| G2Affine::rand(rng)).collect::<Vec<_>>();
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let points_t = points
+            .iter()
+            .map(|p| G2Target::constant(&mut builder, *p))
+            .collect::<Vec<_>>();
+        G2Target::assert_all_in_subgroup(&mut builder, &points_t);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    // assert_in_subgroup alone, which also covers the curve equation
+    fn check_on_curve_and_subgroup(p: G2Affine) {
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let p_t = G2Target::constant(&mut builder, p);
+        p_t.assert_in_subgroup(&mut builder);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    fn check_on_curve(p: G2Affine) {
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let p_t = G2Target::empty(&mut builder);
+        let vec = p_t.to_vec();
+        assert_eq!(vec.len(), 33);
+        let q_t = G2Target::from_vec(&mut builder, &vec);
+        q_t.assert_on_curve(&mut builder);
+
+        let mut pw = PartialWitness::new();
+        p_t.set_witness(&mut pw, &p);
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_assert_on_curve_accepts_flagged_identity() {
+        check_on_curve(G2Affine::identity());
+        check_on_curve(G2Affine::rand(&mut rand::thread_rng()));
+    }
+
+    // the identity's coordinates without the flag
+    #[test]
+    #[should_panic]
+    fn test_assert_on_curve_rejects_unflagged_zero() {
+        check_on_curve(G2Affine::new_unchecked(Fq2::ZERO, Fq2::ZERO));
+    }
+
+    #[test]
+    fn test_assert_on_curve_and_in_subgroup() {
+        let rng = &mut rand::thread_rng();
+        check_on_curve_and_subgroup(G2Affine::rand(rng));
+        check_on_curve_and_subgroup(G2Affine::identity());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_assert_on_curve_rejects_off_curve_point() {
+        let rng = &mut rand::thread_rng();
+        let p = G2Affine::rand(rng);
+        let bad = G2Affine::new_unchecked(p.x, p.y + Fq2::ONE);
+        assert!(!bad.is_on_curve());
+        check_on_curve_and_subgroup(bad);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_assert_in_subgroup_rejects_cofactor_point() {
+        let bad = map_to_g2_without_cofactor_mul(Fq2::from(3u64));
+        assert!(bad.is_on_curve());
+        assert!(!bad.is_in_correct_subgroup_assuming_on_curve());
+        check_on_curve_and_subgroup(bad);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_assert_all_in_subgroup_rejects_bad_point() {
+        let rng = &mut rand::thread_rng();
+        // on the curve, but the cofactor was not cleared
+        let bad = map_to_g2_without_cofactor_mul(Fq2::from(3u64));
+        assert!(bad.is_on_curve());
+        assert!(!bad.is_in_correct_subgroup_assuming_on_curve());
+        let points = vec![G2Affine::rand(rng), bad, G2Affine::rand(rng)];
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let points_t = points
+            .iter()
+            .map(|p| G2Target::constant(&mut builder, *p))
+            .collect::<Vec<_>>();
+        G2Target::assert_all_in_subgroup(&mut builder, &points_t);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw);
+    }
 }
@@ -0,0 +1,449 @@
+use itertools::Itertools;
+use plonky2::{
+    field::extension::Extendable,
+    hash::hash_types::RichField,
+    iop::target::{BoolTarget, Target},
+    plonk::circuit_builder::CircuitBuilder,
+};
+use plonky2_crypto::u32::gadgets::arithmetic_u32::U32Target;
+use plonky2_ecdsa::gadgets::biguint::BigUintTarget;
+
+use crate::fields::{fq2_target::Fq2Target, fq_target::FqTarget};
+
+// hash_to_field of RFC 9380 (section 5) for BN254 with expand_message_xmd over SHA-256, as
+// in the BN254 suites of gnark and other implementations: k = 128, so each Fq element is
+// taken from L = ceil((254 + 128) / 8) = 48 uniform bytes, read big-endian (OS2IP) mod p
+
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+const SHA256_H: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+// SHA-256 output and expand_message_xmd input block sizes in bytes
+const B_IN_BYTES: usize = 32;
+const S_IN_BYTES: usize = 64;
+
+// uniform bytes per Fq element
+pub const L: usize = 48;
+
+// bits of a byte / of a 32-bit word, least significant first
+type Byte = [BoolTarget; 8];
+type Word = [BoolTarget; 32];
+
+fn constant_byte<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    b: u8,
+) -> Byte {
+    core::array::from_fn(|i| builder.constant_bool((b >> i) & 1 == 1))
+}
+
+fn constant_word<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    w: u32,
+) -> Word {
+    core::array::from_fn(|i| builder.constant_bool((w >> i) & 1 == 1))
+}
+
+fn xor<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    a: BoolTarget,
+    b: BoolTarget,
+) -> BoolTarget {
+    // a + b - 2ab
+    let a_plus_b = builder.add(a.target, b.target);
+    let c = builder.arithmetic(-F::TWO, F::ONE, a.target, b.target, a_plus_b);
+    BoolTarget::new_unsafe(c)
+}
+
+fn xor_words<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    words: &[Word],
+) -> Word {
+    core::array::from_fn(|i| {
+        words[1..]
+            .iter()
+            .fold(words[0][i], |acc, w| xor(builder, acc, w[i]))
+    })
+}
+
+fn rotr(w: &Word, n: usize) -> Word {
+    core::array::from_fn(|i| w[(i + n) % 32])
+}
+
+fn shr<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    w: &Word,
+    n: usize,
+) -> Word {
+    let zero = builder._false();
+    core::array::from_fn(|i| if i + n < 32 { w[i + n] } else { zero })
+}
+
+// sum mod 2^32, range checking the full sum and dropping the carry bits
+fn add_words<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    words: &[Word],
+) -> Word {
+    let sums = words.iter().map(|w| builder.le_sum(w.iter())).collect_vec();
+    let sum = builder.add_many(sums);
+    let carry_bits = words.len().next_power_of_two().trailing_zeros() as usize;
+    let bits = builder.split_le(sum, 32 + carry_bits);
+    core::array::from_fn(|i| bits[i])
+}
+
+// big-endian word from 4 bytes, as SHA-256 reads its input
+fn word_from_bytes(bytes: &[Byte]) -> Word {
+    core::array::from_fn(|i| bytes[3 - i / 8][i % 8])
+}
+
+fn compress<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    state: &[Word; 8],
+    block: &[Byte],
+) -> [Word; 8] {
+    let mut w = block.chunks(4).map(word_from_bytes).collect_vec();
+    for t in 16..64 {
+        let s0 = [rotr(&w[t - 15], 7), rotr(&w[t - 15], 18), shr(builder, &w[t - 15], 3)];
+        let s0 = xor_words(builder, &s0);
+        let s1 = [rotr(&w[t - 2], 17), rotr(&w[t - 2], 19), shr(builder, &w[t - 2], 10)];
+        let s1 = xor_words(builder, &s1);
+        let wt = add_words(builder, &[w[t - 16], s0, w[t - 7], s1]);
+        w.push(wt);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for t in 0..64 {
+        let s1 = xor_words(builder, &[rotr(&e, 6), rotr(&e, 11), rotr(&e, 25)]);
+        // ch(e, f, g) = g + e (f - g)
+        let ch: Word = core::array::from_fn(|i| {
+            let f_minus_g = builder.sub(f[i].target, g[i].target);
+            BoolTarget::new_unsafe(builder.mul_add(e[i].target, f_minus_g, g[i].target))
+        });
+        let k = constant_word(builder, SHA256_K[t]);
+        let t1 = add_words(builder, &[h, s1, ch, k, w[t]]);
+        let s0 = xor_words(builder, &[rotr(&a, 2), rotr(&a, 13), rotr(&a, 22)]);
+        // maj(a, b, c) = bc + a (b xor c)
+        let maj: Word = core::array::from_fn(|i| {
+            let bc = builder.mul(b[i].target, c[i].target);
+            let b_plus_c = builder.add(b[i].target, c[i].target);
+            let b_xor_c = builder.arithmetic(-F::TWO, F::ONE, b[i].target, c[i].target, b_plus_c);
+            BoolTarget::new_unsafe(builder.mul_add(a[i].target, b_xor_c, bc))
+        });
+        let t2 = add_words(builder, &[s0, maj]);
+        h = g;
+        g = f;
+        f = e;
+        e = add_words(builder, &[d, t1]);
+        d = c;
+        c = b;
+        b = a;
+        a = add_words(builder, &[t1, t2]);
+    }
+
+    let working = [a, b, c, d, e, f, g, h];
+    core::array::from_fn(|i| add_words(builder, &[state[i], working[i]]))
+}
+
+// SHA-256 of a message whose length is fixed when building, so the padding is constant
+fn sha256<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    msg: &[Byte],
+) -> Vec<Byte> {
+    let mut padded = msg.to_vec();
+    padded.push(constant_byte(builder, 0x80));
+    while padded.len() % S_IN_BYTES != S_IN_BYTES - 8 {
+        padded.push(constant_byte(builder, 0));
+    }
+    for b in ((msg.len() as u64) * 8).to_be_bytes() {
+        padded.push(constant_byte(builder, b));
+    }
+
+    let mut state = SHA256_H.map(|h| constant_word(builder, h));
+    for block in padded.chunks(S_IN_BYTES) {
+        state = compress(builder, &state, block);
+    }
+    state
+        .iter()
+        .flat_map(|w| (0..4).map(move |j| core::array::from_fn(|i| w[8 * (3 - j) + i])))
+        .collect()
+}
+
+// expand_message_xmd of RFC 9380 section 5.3.1 on bit-decomposed bytes
+fn expand_message_xmd_bytes<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    msg: &[Byte],
+    dst: &[u8],
+    len_in_bytes: usize,
+) -> Vec<Byte> {
+    let ell = (len_in_bytes + B_IN_BYTES - 1) / B_IN_BYTES;
+    assert!(ell <= 255, "len_in_bytes too large");
+    assert!(len_in_bytes <= 65535, "len_in_bytes too large");
+    assert!(dst.len() <= 255, "DST longer than 255 bytes");
+
+    let dst_prime = dst
+        .iter()
+        .copied()
+        .chain([dst.len() as u8])
+        .map(|b| constant_byte(builder, b))
+        .collect_vec();
+
+    // msg_prime = Z_pad || msg || I2OSP(len_in_bytes, 2) || I2OSP(0, 1) || DST_prime
+    let mut msg_prime = (0..S_IN_BYTES)
+        .map(|_| constant_byte(builder, 0))
+        .collect_vec();
+    msg_prime.extend_from_slice(msg);
+    for b in (len_in_bytes as u16).to_be_bytes() {
+        msg_prime.push(constant_byte(builder, b));
+    }
+    msg_prime.push(constant_byte(builder, 0));
+    msg_prime.extend_from_slice(&dst_prime);
+    let b_0 = sha256(builder, &msg_prime);
+
+    let mut uniform_bytes: Vec<Byte> = vec![];
+    let mut b_i = b_0.clone();
+    for i in 1..=ell {
+        // b_1 = H(b_0 || 1 || DST_prime), b_i = H((b_0 xor b_(i - 1)) || i || DST_prime)
+        let mut input = if i == 1 {
+            b_0.clone()
+        } else {
+            b_0.iter()
+                .zip(&b_i)
+                .map(|(x, y)| core::array::from_fn(|k| xor(builder, x[k], y[k])))
+                .collect_vec()
+        };
+        input.push(constant_byte(builder, i as u8));
+        input.extend_from_slice(&dst_prime);
+        b_i = sha256(builder, &input);
+        uniform_bytes.extend_from_slice(&b_i);
+    }
+    uniform_bytes.truncate(len_in_bytes);
+    uniform_bytes
+}
+
+// splits each message byte into bits, which also range checks it to 8 bits
+fn bytes_to_bits<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    msg: &[Target],
+) -> Vec<Byte> {
+    msg.iter()
+        .map(|&b| {
+            let bits = builder.split_le(b, 8);
+            core::array::from_fn(|i| bits[i])
+        })
+        .collect()
+}
+
+// expand_message_xmd with SHA-256. msg holds one byte per target (range checked here) and
+// the output is len_in_bytes byte targets
+pub fn expand_message_xmd<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    msg: &[Target],
+    dst: &[u8],
+    len_in_bytes: usize,
+) -> Vec<Target> {
+    let msg = bytes_to_bits(builder, msg);
+    expand_message_xmd_bytes(builder, &msg, dst, len_in_bytes)
+        .iter()
+        .map(|b| builder.le_sum(b.iter()))
+        .collect()
+}
+
+// OS2IP of L big-endian bytes, reduced mod p
+fn fq_from_uniform_bytes<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    bytes: &[Byte],
+) -> FqTarget<F, D> {
+    let bits = bytes.iter().rev().flatten().copied().collect_vec();
+    let limbs = bits
+        .chunks(32)
+        .map(|chunk| U32Target(builder.le_sum(chunk.iter())))
+        .collect_vec();
+    FqTarget::reduce_from_biguint_target(builder, &BigUintTarget { limbs })
+}
+
+// hash_to_field with m = 1: count Fq elements, e.g. 2 for a random-oracle hash to G1
+pub fn hash_to_fq<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    msg: &[Target],
+    dst: &[u8],
+    count: usize,
+) -> Vec<FqTarget<F, D>> {
+    let msg = bytes_to_bits(builder, msg);
+    let uniform_bytes = expand_message_xmd_bytes(builder, &msg, dst, count * L);
+    uniform_bytes
+        .chunks(L)
+        .map(|bytes| fq_from_uniform_bytes(builder, bytes))
+        .collect()
+}
+
+// hash_to_field with m = 2: count Fq2 elements c0 + c1 u, e.g. 2 for a random-oracle
+// hash to G2. Coefficient j of element i comes from bytes L * (j + 2i) .. L * (j + 2i + 1)
+pub fn hash_to_fq2<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    msg: &[Target],
+    dst: &[u8],
+    count: usize,
+) -> Vec<Fq2Target<F, D>> {
+    let msg = bytes_to_bits(builder, msg);
+    let uniform_bytes = expand_message_xmd_bytes(builder, &msg, dst, count * 2 * L);
+    uniform_bytes
+        .chunks(2 * L)
+        .map(|bytes| {
+            let c0 = fq_from_uniform_bytes(builder, &bytes[..L]);
+            let c1 = fq_from_uniform_bytes(builder, &bytes[L..]);
+            Fq2Target { coeffs: [c0, c1] }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use ark_bn254::{Fq, Fq2};
+    use plonky2::{
+        field::{goldilocks_field::GoldilocksField, types::Field},
+        iop::{
+            target::Target,
+            witness::{PartialWitness, WitnessWrite},
+        },
+        plonk::{
+            circuit_builder::CircuitBuilder, circuit_data::CircuitConfig,
+            config::PoseidonGoldilocksConfig,
+        },
+    };
+
+    use crate::fields::{fq2_target::Fq2Target, fq_target::FqTarget};
+
+    use super::{expand_message_xmd, hash_to_fq, hash_to_fq2};
+
+    type F = GoldilocksField;
+    type C = PoseidonGoldilocksConfig;
+    const D: usize = 2;
+
+    const EXPANDER_DST: &[u8] = b"QUUX-V01-CS02-with-expander-SHA256-128";
+
+    fn message_targets(
+        builder: &mut CircuitBuilder<F, D>,
+        pw: &mut PartialWitness<F>,
+        msg: &[u8],
+    ) -> Vec<Target> {
+        msg.iter()
+            .map(|&b| {
+                let t = builder.add_virtual_target();
+                pw.set_target(t, F::from_canonical_u8(b));
+                t
+            })
+            .collect()
+    }
+
+    fn check_expand_message_xmd(msg: &[u8], len_in_bytes: usize, expected: &str) {
+        let expected = hex::decode(expected).unwrap();
+        assert_eq!(expected.len(), len_in_bytes);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let mut pw = PartialWitness::new();
+        let msg_t = message_targets(&mut builder, &mut pw, msg);
+        let output_t = expand_message_xmd(&mut builder, &msg_t, EXPANDER_DST, len_in_bytes);
+        for (t, b) in output_t.into_iter().zip(expected) {
+            let b = builder.constant(F::from_canonical_u8(b));
+            builder.connect(t, b);
+        }
+
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    // RFC 9380 appendix K.1
+    #[test]
+    fn test_expand_message_xmd_rfc_vectors() {
+        check_expand_message_xmd(
+            b"",
+            0x20,
+            "68a985b87eb6b46952128911f2a4412bbc302a9d759667f87f7a21d803f07235",
+        );
+        check_expand_message_xmd(
+            b"abc",
+            0x20,
+            "d8ccab23b5985ccea865c6c97b6e5b8350e794e603b4b97902f53a8a0d605615",
+        );
+        check_expand_message_xmd(
+            b"abcdef0123456789",
+            0x20,
+            "eff31487c770a893cfb36f912fbfcbff40d5661771ca4b2cb4eafe524333f5c1",
+        );
+        check_expand_message_xmd(
+            b"abc",
+            0x80,
+            "abba86a6129e366fc877aab32fc4ffc70120d8996c88aee2fe4b32d6c7b6437a\
+             647e6c3163d40b76a73cf6a5674ef1d890f95b664ee0afa5359a5c4e07985635\
+             bbecbac65d747d3d2da7ec2b8221b17b0ca9dc8a1ac1c07ea6a1e60583e2cb00\
+             058e77b7b72a298425cd1b941ad4ec65e8afc50303a22c0f99b0509b4c895f40",
+        );
+    }
+
+    // RFC 9380 defines no BN254 suite. The expected values are OS2IP(uniform bytes) mod p
+    // computed off-circuit with the K.1-conformant expander above, using the DSTs of the
+    // BN254 SVDW suites
+    #[test]
+    fn test_hash_to_fq() {
+        let dst = b"QUUX-V01-CS02-with-BN254G1_XMD:SHA-256_SVDW_RO_";
+        let expected = [
+            "7951370986911800256774597109927097176311261202951929331835478768207980370345",
+            "8293556689416303717881563281438712057465092967957999993252567763605862533321",
+        ];
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let mut pw = PartialWitness::new();
+        let msg_t = message_targets(&mut builder, &mut pw, b"abc");
+        let u_t = hash_to_fq(&mut builder, &msg_t, dst, 2);
+        for (u_t, u) in u_t.iter().zip(expected) {
+            let u_expected_t = FqTarget::constant(&mut builder, Fq::from_str(u).unwrap());
+            FqTarget::connect(&mut builder, u_t, &u_expected_t);
+        }
+
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_hash_to_fq2() {
+        let dst = b"QUUX-V01-CS02-with-BN254G2_XMD:SHA-256_SVDW_RO_";
+        let expected = [
+            (
+                "15963713818282906360305918686195491545577210390832157279818305179904408824931",
+                "2166278439352519416731010325104738631510195416620895094682522641528929475020",
+            ),
+            (
+                "12752967732566665017975022503761080419696068755373050496264700974774108086129",
+                "20655422394809824901799481664662586419100706577355794400212187554951433717414",
+            ),
+        ];
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let mut pw = PartialWitness::new();
+        let msg_t = message_targets(&mut builder, &mut pw, b"abc");
+        let u_t = hash_to_fq2(&mut builder, &msg_t, dst, 2);
+        for (u_t, (c0, c1)) in u_t.iter().zip(expected) {
+            let u = Fq2::new(Fq::from_str(c0).unwrap(), Fq::from_str(c1).unwrap());
+            let u_expected_t = Fq2Target::constant(&mut builder, u);
+            Fq2Target::connect(&mut builder, u_t, &u_expected_t);
+        }
+
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+}
@@ -156,7 +156,9 @@ pub fn map_to_g2_without_cofactor_mul_circuit<F: RichField + Extendable<D>, cons
     let sgn_u = u.sgn0(builder);
     let y = gx.sqrt_with_sgn(builder, sgn_u);
 
-    G2Target::new(x, y)
+    // the map lands on an affine point of the curve, never the identity
+    let infinity = builder._false();
+    G2Target::new(x, y, infinity)
 }
 
 #[cfg(test)]
@@ -69,9 +69,16 @@ use plonky2_ecdsa::gadgets::{
     },
 };
 
-use crate::fields::bn254base::Bn254Base;
+use crate::fields::{
+    bn254base::Bn254Base,
+    bn254scalar::Bn254Scalar,
+    fq12_target::{Fq12AssertEqualGenerator, Fq12ExpGenerator, Fq12InverseGenerator},
+    fq2_target::{Fq2InverseGenerator, Fq2SqrtGenerator},
+    fq_target::{FqSqrtFlagGenerator, FqSqrtGenerator},
+};
 pub mod g1curve_target;
 pub mod g2curve_target;
+pub mod hash_to_field;
 pub mod map_to_g2;
 
 /// Sets RUST_LOG=debug and initializes the logger
@@ -90,6 +97,9 @@ pub(crate) fn init_logging() {
     }
 }
 
+/// Generator registry for circuits built with this crate: plonky2's default generators,
+/// the u32 / nonnative gadgets and every generator defined here (Fq sqrt, Fq2 inverse and
+/// sqrt, Fq12 inverse and exp), so pairing circuits can be serialized without listing them.
 pub struct BN254GeneratorSerializer<C: GenericConfig<D>, const D: usize> {
     pub _phantom: PhantomData<C>,
 }
@@ -137,7 +147,20 @@ where
         U32AddManyGenerator<F,D>,
         U32SubtractionGenerator<F,D>,
         U32ArithmeticGenerator<F,D>,
-        ComparisonGenerator<F,D>
+        ComparisonGenerator<F,D>,
+        // scalar field nonnative generators
+        NonNativeSubtractionGenerator<F,D,Bn254Scalar>,
+        NonNativeInverseGenerator<F,D,Bn254Scalar>,
+        NonNativeMultiplicationGenerator<F,D,Bn254Scalar>,
+        NonNativeAdditionGenerator<F,D,Bn254Scalar>,
+        // this crate's generators
+        FqSqrtGenerator<F,D>,
+        FqSqrtFlagGenerator<F,D>,
+        Fq2InverseGenerator<F,D>,
+        Fq2SqrtGenerator<F,D>,
+        Fq12InverseGenerator<F,D>,
+        Fq12ExpGenerator<F,D>,
+        Fq12AssertEqualGenerator
     }
 }
 
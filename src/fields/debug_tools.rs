@@ -13,7 +13,7 @@ use plonky2::{
     util::serialization::{Buffer, IoError},
 };
 
-use crate::fields::fq_target::FqTarget;
+use crate::fields::{fq12_target::Fq12Target, fq_target::FqTarget};
 
 use super::native::MyFq12;
 
@@ -88,6 +88,86 @@ pub fn print_fq_target<F: RichField + Extendable<D>, const D: usize>(
     builder.add_simple_generator(generator);
 }
 
+// the coefficients where lhs and rhs differ, with both values and lhs - rhs as integers,
+// or None when they are equal
+pub fn fq12_diff_report(lhs: &MyFq12, rhs: &MyFq12) -> Option<String> {
+    let lines = lhs
+        .coeffs
+        .iter()
+        .zip(rhs.coeffs.iter())
+        .enumerate()
+        .filter(|(_, (l, r))| l != r)
+        .map(|(i, (l, r))| {
+            let l_big: BigUint = (*l).into();
+            let r_big: BigUint = (*r).into();
+            let diff: BigUint = (*l - *r).into();
+            format!("  coefficient {i}: {l_big} != {r_big} (lhs - rhs = {diff} mod p)")
+        })
+        .collect_vec();
+    if lines.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "Fq12 values differ at {} coefficients:\n{}",
+        lines.len(),
+        lines.join("\n")
+    ))
+}
+
+#[derive(Debug, Clone)]
+struct Fq12DiffGenerator<F: RichField + Extendable<D>, const D: usize> {
+    lhs: Fq12Target<F, D>,
+    rhs: Fq12Target<F, D>,
+}
+
+impl<F: PrimeField64 + RichField + Extendable<D>, const D: usize> SimpleGenerator<F, D>
+    for Fq12DiffGenerator<F, D>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        self.lhs
+            .to_vec()
+            .into_iter()
+            .chain(self.rhs.to_vec())
+            .collect_vec()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, _out_buffer: &mut GeneratedValues<F>) {
+        let lhs: MyFq12 = self.lhs.get_witness(witness).into();
+        let rhs: MyFq12 = self.rhs.get_witness(witness).into();
+        if let Some(report) = fq12_diff_report(&lhs, &rhs) {
+            eprintln!("{report}");
+        }
+    }
+
+    fn id(&self) -> std::string::String {
+        "Fq12DiffGenerator".to_string()
+    }
+
+    // debug only, a circuit holding it is not meant to be serialized
+    fn serialize(&self, _: &mut Vec<u8>, _: &CommonCircuitData<F, D>) -> Result<(), IoError> {
+        Err(IoError)
+    }
+    fn deserialize(_: &mut Buffer, _: &CommonCircuitData<F, D>) -> Result<Self, IoError> {
+        Err(IoError)
+    }
+}
+
+// witness-time only: prints the differing coefficients of lhs and rhs to stderr, adds no
+// gates and no constraints. A connect merges the two sides into one value, so pair this
+// with Fq12Target::assert_equal or a flag, not with connect. The generator is not
+// serializable (serializing it returns an error), so remove the call before serializing a
+// circuit
+pub fn debug_assert_equal_fq12<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    lhs: &Fq12Target<F, D>,
+    rhs: &Fq12Target<F, D>,
+) {
+    builder.add_simple_generator(Fq12DiffGenerator {
+        lhs: lhs.clone(),
+        rhs: rhs.clone(),
+    });
+}
+
 pub fn print_ark_fq(x: Fq, annotation: String) {
     let values_biguint: BigUint = x.into();
     let values_hex = hex::encode(values_biguint.to_bytes_be());
@@ -116,22 +196,27 @@ pub fn restore_fq(values_hex: String) -> Fq {
 
 #[cfg(test)]
 mod tests {
-    use ark_bn254::{Fq, G2Affine};
+    use ark_bn254::{Fq, Fq12, G2Affine};
     use ark_ec::AffineRepr;
+    use ark_ff::PrimeField;
     use ark_std::UniformRand;
     use num_bigint::BigUint;
     use plonky2::{
         field::goldilocks_field::GoldilocksField,
-        iop::witness::PartialWitness,
+        iop::{generator::SimpleGenerator, witness::PartialWitness},
         plonk::{
             circuit_builder::CircuitBuilder, circuit_data::CircuitConfig,
             config::PoseidonGoldilocksConfig,
         },
+        util::serialization::Buffer,
     };
 
-    use crate::fields::fq_target::FqTarget;
+    use crate::fields::{fq12_target::Fq12Target, fq_target::FqTarget, native::MyFq12};
 
-    use super::{print_ark_fq, print_fq_target};
+    use super::{
+        debug_assert_equal_fq12, fq12_diff_report, print_ark_fq, print_fq_target,
+        Fq12DiffGenerator,
+    };
 
     type F = GoldilocksField;
     type C = PoseidonGoldilocksConfig;
@@ -151,6 +236,65 @@ mod tests {
         let _proof = data.prove(pw);
     }
 
+    #[test]
+    fn test_fq12_diff_report() {
+        let rng = &mut rand::thread_rng();
+        let x: MyFq12 = Fq12::rand(rng).into();
+        let mut y = x;
+        y.coeffs[5] += Fq::from(2);
+        y.coeffs[11] += Fq::from(3);
+
+        assert_eq!(fq12_diff_report(&x, &x), None);
+        let report = fq12_diff_report(&x, &y).unwrap();
+        assert!(report.starts_with("Fq12 values differ at 2 coefficients"));
+        assert!(report.contains("coefficient 5:"));
+        assert!(report.contains("coefficient 11:"));
+        let diff = BigUint::from(Fq::MODULUS) - 2u32;
+        assert!(report.contains(&format!("lhs - rhs = {diff} mod p")));
+    }
+
+    // differing values only get reported: no gates, and the proof still goes through
+    #[test]
+    fn test_debug_assert_equal_fq12() {
+        let rng = &mut rand::thread_rng();
+        let x = Fq12::rand(rng);
+        let mut y: MyFq12 = x.into();
+        y.coeffs[5] += Fq::from(2);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::empty(&mut builder);
+        let y_t = Fq12Target::empty(&mut builder);
+        let before = builder.num_gates();
+        debug_assert_equal_fq12(&mut builder, &x_t, &y_t);
+        x_t.debug_assert_equal(&mut builder, &x_t);
+        assert_eq!(builder.num_gates(), before);
+
+        let mut pw = PartialWitness::new();
+        x_t.set_witness(&mut pw, &x);
+        y_t.set_witness(&mut pw, &y.into());
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_fq12_diff_generator_is_not_serializable() {
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let generator = Fq12DiffGenerator {
+            lhs: Fq12Target::empty(&mut builder),
+            rhs: Fq12Target::empty(&mut builder),
+        };
+        let data = builder.build::<C>();
+        let mut bytes = vec![];
+        assert!(SimpleGenerator::<F, D>::serialize(&generator, &mut bytes, &data.common).is_err());
+        let res = <Fq12DiffGenerator<F, D> as SimpleGenerator<F, D>>::deserialize(
+            &mut Buffer::new(&bytes),
+            &data.common,
+        );
+        assert!(res.is_err());
+    }
+
     #[test]
     fn test_back_forward() {
         let rng = &mut rand::thread_rng();
@@ -1,7 +1,8 @@
-use ark_bn254::{Fq, Fq12};
-use ark_ff::Field;
+use ark_bn254::{Fq, Fq12, Fq2, Fr};
+use ark_ff::{Field, PrimeField};
 use itertools::Itertools;
 use num_bigint::BigUint;
+use num_traits::Zero;
 use plonky2::{
     field::extension::Extendable,
     hash::hash_types::RichField,
@@ -13,45 +14,147 @@ use plonky2::{
     plonk::{circuit_builder::CircuitBuilder, circuit_data::CommonCircuitData},
     util::serialization::{Buffer, IoError, Read, Write},
 };
+use plonky2_crypto::u32::gadgets::arithmetic_u32::U32Target;
 use plonky2_ecdsa::gadgets::{
-    biguint::{GeneratedValuesBigUint, WitnessBigUint},
+    biguint::{BigUintTarget, CircuitBuilderBiguint, GeneratedValuesBigUint, WitnessBigUint},
     nonnative::CircuitBuilderNonNative,
 };
 
 use crate::{
     fields::{
+        debug_tools::debug_assert_equal_fq12,
+        fq2_target::Fq2Target,
+        fq6_target::Fq6Target,
         fq_target::FqTarget,
-        native::{from_biguint_to_fq, MyFq12},
+        native::{frobenius_coeffs, from_biguint_to_fq, MyFq12},
     },
     utils::get_u256_biguint,
 };
 
+// an Fq12Target built from coefficients of the wrong shape
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fq12ShapeError {
+    // the number of coefficients given, which is not 12
+    CoeffCount(usize),
+    // coefficient index has more limbs than the max an FqTarget holds
+    LimbCount {
+        index: usize,
+        limbs: usize,
+        max: usize,
+    },
+}
+
+impl std::fmt::Display for Fq12ShapeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CoeffCount(n) => write!(f, "Fq12Target needs 12 coefficients, got {n}"),
+            Self::LimbCount { index, limbs, max } => write!(
+                f,
+                "Fq12Target coefficient {index} has {limbs} limbs, at most {max} are allowed"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Fq12ShapeError {}
+
+// c0 + c1 w in the tower Fq12 = Fq6[w] / (w^2 - v), as arkworks' Fq12. The flat
+// coefficients of coeffs / from_coeffs (and of to_vec, the public inputs and serialization)
+// are a reordering of the same targets, see MyFq12
 #[derive(Clone, Debug, Default)]
 pub struct Fq12Target<F: RichField + Extendable<D>, const D: usize> {
-    pub coeffs: [FqTarget<F, D>; 12],
+    pub c0: Fq6Target<F, D>,
+    pub c1: Fq6Target<F, D>,
 }
 
 impl<F: RichField + Extendable<D>, const D: usize> Fq12Target<F, D> {
     pub fn empty(builder: &mut CircuitBuilder<F, D>) -> Self {
-        let coeffs = [(); 12]
-            .iter()
-            .map(|_| FqTarget::empty(builder))
-            .collect_vec()
-            .try_into()
-            .unwrap();
-        Fq12Target { coeffs }
+        Self::from_coeffs(core::array::from_fn(|_| FqTarget::empty(builder)))
+    }
+
+    // the 12 flat coefficients in MyFq12 order: coefficient i is that of w^(i % 6) u^(i / 6)
+    pub fn coeffs(&self) -> [FqTarget<F, D>; 12] {
+        core::array::from_fn(|i| {
+            let (half, k, j) = MyFq12::index_to_tower_position(i);
+            [&self.c0, &self.c1][half].coeffs[k].coeffs[j].clone()
+        })
+    }
+
+    pub fn from_coeffs(coeffs: [FqTarget<F, D>; 12]) -> Self {
+        let mut halves: [Fq6Target<F, D>; 2] = Default::default();
+        for (i, c) in coeffs.into_iter().enumerate() {
+            let (half, k, j) = MyFq12::index_to_tower_position(i);
+            halves[half].coeffs[k].coeffs[j] = c;
+        }
+        let [c0, c1] = halves;
+        Self { c0, c1 }
     }
 
     pub fn new(coeffs: Vec<FqTarget<F, D>>) -> Self {
-        Fq12Target {
-            coeffs: coeffs.try_into().unwrap(),
+        Self::try_new(coeffs).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    // checks the shape up front so a bad input fails here with a description rather than
+    // later in an array conversion or in connect_nonnative. Coefficients may have fewer
+    // than NUM_LIMBS limbs (small constants do), never more
+    pub fn try_new(coeffs: Vec<FqTarget<F, D>>) -> Result<Self, Fq12ShapeError> {
+        let num_limbs = FqTarget::<F, D>::NUM_LIMBS;
+        for (index, c) in coeffs.iter().enumerate() {
+            let limbs = c.to_limbs_without_pad().len();
+            if limbs > num_limbs {
+                return Err(Fq12ShapeError::LimbCount {
+                    index,
+                    limbs,
+                    max: num_limbs,
+                });
+            }
         }
+        let coeffs = coeffs
+            .try_into()
+            .map_err(|coeffs: Vec<_>| Fq12ShapeError::CoeffCount(coeffs.len()))?;
+        Ok(Self::from_coeffs(coeffs))
     }
 
     pub fn connect(builder: &mut CircuitBuilder<F, D>, lhs: &Self, rhs: &Self) {
-        for i in 0..12 {
-            builder.connect_nonnative(&lhs.coeffs[i].target, &rhs.coeffs[i].target);
+        Fq6Target::connect(builder, &lhs.c0, &rhs.c0);
+        Fq6Target::connect(builder, &lhs.c1, &rhs.c1);
+    }
+
+    // connect to a constant coefficient by coefficient, see FqTarget::connect_to_constant.
+    // No cheaper than connect to Fq12Target::constant, but a zero coefficient also accepts p
+    pub fn connect_to_constant(&self, builder: &mut CircuitBuilder<F, D>, c: &Fq12) {
+        let c: MyFq12 = (*c).into();
+        for (x, c) in self.coeffs().iter().zip(c.coeffs.iter()) {
+            x.connect_to_constant(builder, c);
+        }
+    }
+
+    // connect with a label for debugging: when the two sides differ, witness generation
+    // panics with the label and the differing coefficients. Equality is enforced limb-wise
+    // as self = other + diff with diff = 0, where diff is written by the checking generator:
+    // a plain connect would merge the two sides, and the conflicting write would then fail
+    // before any check could run
+    pub fn assert_equal(&self, builder: &mut CircuitBuilder<F, D>, other: &Self, label: &str) {
+        let lhs = self.coeffs().iter().flat_map(|c| c.to_limbs(builder)).collect_vec();
+        let rhs = other.coeffs().iter().flat_map(|c| c.to_limbs(builder)).collect_vec();
+        let diffs = builder.add_virtual_targets(lhs.len());
+        for ((l, r), diff) in lhs.iter().zip(rhs.iter()).zip(diffs.iter()) {
+            let sum = builder.add(*r, *diff);
+            builder.connect(sum, *l);
+            builder.assert_zero(*diff);
         }
+        builder.add_simple_generator(Fq12AssertEqualGenerator {
+            lhs,
+            rhs,
+            diffs,
+            label: label.to_string(),
+        });
+    }
+
+    // see debug_tools::debug_assert_equal_fq12: reports differing coefficients at witness
+    // time without adding any constraint
+    pub fn debug_assert_equal(&self, builder: &mut CircuitBuilder<F, D>, other: &Self) {
+        debug_assert_equal_fq12(builder, self, other);
     }
 
     pub fn select(
@@ -60,78 +163,197 @@ impl<F: RichField + Extendable<D>, const D: usize> Fq12Target<F, D> {
         b: &Self,
         flag: &BoolTarget,
     ) -> Self {
-        let selected = a
-            .coeffs
+        Self {
+            c0: Fq6Target::select(builder, &a.c0, &b.c0, flag),
+            c1: Fq6Target::select(builder, &a.c1, &b.c1, flag),
+        }
+    }
+
+    // candidates[index] for index = sum selector_bits[i] 2^i (little-endian), as a balanced
+    // tree of 2^k - 1 selects: level i halves the candidates using selector_bits[i]. The
+    // bits are taken as already boolean, so unlike random_access there is no range check
+    pub fn mux(
+        builder: &mut CircuitBuilder<F, D>,
+        selector_bits: &[BoolTarget],
+        candidates: &[Self],
+    ) -> Self {
+        assert_eq!(
+            candidates.len(),
+            1 << selector_bits.len(),
+            "mux needs 2^k candidates for k selector bits"
+        );
+        let mut level = candidates.to_vec();
+        for bit in selector_bits {
+            level = level
+                .chunks(2)
+                .map(|pair| Self::select(builder, &pair[1], &pair[0], bit))
+                .collect_vec();
+        }
+        level.pop().unwrap()
+    }
+
+    // table[index] for an index known only at proving time, one plonky2 random_access per
+    // limb. The table is padded with zeros to a power of two length; the index is then also
+    // constrained below table.len() so it cannot select padding
+    pub fn random_access(
+        builder: &mut CircuitBuilder<F, D>,
+        index: Target,
+        table: &[Self],
+    ) -> Self {
+        assert!(!table.is_empty(), "random_access needs a non-empty table");
+        let padded_len = table.len().next_power_of_two();
+        if padded_len != table.len() {
+            // index is in 0..table.len() iff prod (index - i) = 0
+            let diffs = (0..table.len())
+                .map(|i| {
+                    let i = builder.constant(F::from_canonical_usize(i));
+                    builder.sub(index, i)
+                })
+                .collect_vec();
+            let prod = builder.mul_many(diffs);
+            builder.assert_zero(prod);
+        }
+        let limbs = table
+            .iter()
+            .map(|x| x.coeffs().map(|c| c.to_limbs(builder)))
+            .collect_vec();
+        let zero = builder.zero();
+        let coeffs = (0..12)
+            .map(|k| {
+                let limbs = (0..FqTarget::<F, D>::NUM_LIMBS)
+                    .map(|l| {
+                        let mut column = limbs.iter().map(|x| x[k][l]).collect_vec();
+                        column.resize(padded_len, zero);
+                        U32Target(builder.random_access(index, column))
+                    })
+                    .collect_vec();
+                FqTarget::new(builder.biguint_to_nonnative(&BigUintTarget { limbs }))
+            })
+            .collect_vec();
+        Self::from_coeffs(coeffs.try_into().unwrap())
+    }
+
+    // equality mod p: coefficients are reduced first, so limbs that differ only in
+    // how they are reduced (e.g. p and 0, or x + p read back with from_vec) compare equal
+    pub fn is_equal(&self, builder: &mut CircuitBuilder<F, D>, rhs: &Self) -> BoolTarget {
+        let terms = self
+            .coeffs()
             .iter()
-            .zip(b.coeffs.iter())
-            .map(|(a, b)| FqTarget::select(builder, a, b, flag))
+            .zip(rhs.coeffs().iter())
+            .map(|(a, b)| {
+                let a = a.reduce(builder);
+                let b = b.reduce(builder);
+                a.is_equal(builder, &b).target
+            })
             .collect_vec();
+        let is_equal = builder.mul_many(terms);
 
+        // is_equal is ensured to be 0 or 1, so we can safely convert it to bool.
+        BoolTarget::new_unsafe(is_equal)
+    }
+
+    pub fn is_zero(&self, builder: &mut CircuitBuilder<F, D>) -> BoolTarget {
+        let terms = self
+            .coeffs()
+            .iter()
+            .map(|a| a.reduce(builder).is_zero(builder).target)
+            .collect_vec();
+        let is_zero = builder.mul_many(terms);
+
+        // is_zero is ensured to be 0 or 1, so we can safely convert it to bool.
+        BoolTarget::new_unsafe(is_zero)
+    }
+
+    // self == rhs or self == conj(rhs)
+    pub fn is_equal_up_to_conjugation(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        rhs: &Self,
+    ) -> BoolTarget {
+        let rhs_conj = rhs.conjugate(builder);
+        let is_equal = self.is_equal(builder, rhs);
+        let is_equal_conj = self.is_equal(builder, &rhs_conj);
+        builder.or(is_equal, is_equal_conj)
+    }
+
+    pub fn constant(builder: &mut CircuitBuilder<F, D>, c: Fq12) -> Self {
         Self {
-            coeffs: selected.try_into().unwrap(),
+            c0: Fq6Target::constant(builder, c.c0),
+            c1: Fq6Target::constant(builder, c.c1),
         }
     }
 
-    pub fn constant(builder: &mut CircuitBuilder<F, D>, c: Fq12) -> Self {
-        let c: MyFq12 = c.into();
-        let coeffs = c
-            .coeffs
-            .iter()
-            .map(|x| FqTarget::constant(builder, x.clone()))
-            .collect_vec()
-            .try_into()
-            .unwrap();
-        Self { coeffs }
+    // the zero coefficients all share a single zero constant
+    pub fn zero(builder: &mut CircuitBuilder<F, D>) -> Self {
+        let zero = FqTarget::zero(builder);
+        Self::from_coeffs(core::array::from_fn(|_| zero.clone()))
+    }
+
+    // zero with the constant term set to 1
+    pub fn one(builder: &mut CircuitBuilder<F, D>) -> Self {
+        let one = FqTarget::constant(builder, Fq::ONE);
+        Self::from_fq(builder, &one)
+    }
+
+    // embeds x as the constant term c0.c0.c0, every other coefficient is zero
+    pub fn from_fq(builder: &mut CircuitBuilder<F, D>, x: &FqTarget<F, D>) -> Self {
+        let mut x12 = Self::zero(builder);
+        x12.c0.coeffs[0].coeffs[0] = x.clone();
+        x12
+    }
+
+    // x sits at w^0, i.e. c0.c0
+    pub fn from_fq2(builder: &mut CircuitBuilder<F, D>, x: &Fq2Target<F, D>) -> Self {
+        let mut x12 = Self::zero(builder);
+        x12.c0.coeffs[0] = x.clone();
+        x12
+    }
+
+    // x is c0, as Fq6 is the subfield Fq12 = Fq6[w] is built on
+    pub fn from_fq6(builder: &mut CircuitBuilder<F, D>, x: &Fq6Target<F, D>) -> Self {
+        let mut x12 = Self::zero(builder);
+        x12.c0 = x.clone();
+        x12
     }
 
     pub fn add(&self, builder: &mut CircuitBuilder<F, D>, rhs: &Self) -> Self {
-        let coeffs = self
-            .coeffs
-            .iter()
-            .enumerate()
-            .map(|(i, x)| x.add(builder, &rhs.coeffs[i]))
-            .collect_vec()
-            .try_into()
-            .unwrap();
-        Fq12Target { coeffs }
+        Self {
+            c0: self.c0.add(builder, &rhs.c0),
+            c1: self.c1.add(builder, &rhs.c1),
+        }
     }
 
     pub fn neg(&self, builder: &mut CircuitBuilder<F, D>) -> Self {
-        let coeffs = self
-            .coeffs
-            .iter()
-            .map(|x| x.neg(builder))
-            .collect_vec()
-            .try_into()
-            .unwrap();
-        Fq12Target { coeffs }
+        Self {
+            c0: self.c0.neg(builder),
+            c1: self.c1.neg(builder),
+        }
     }
 
     pub fn sub(&self, builder: &mut CircuitBuilder<F, D>, rhs: &Self) -> Self {
-        let coeffs = self
-            .coeffs
-            .iter()
-            .enumerate()
-            .map(|(i, x)| x.sub(builder, &rhs.coeffs[i]))
-            .collect_vec()
-            .try_into()
-            .unwrap();
-        Fq12Target { coeffs }
+        Self {
+            c0: self.c0.sub(builder, &rhs.c0),
+            c1: self.c1.sub(builder, &rhs.c1),
+        }
     }
 
     pub fn mul(&self, builder: &mut CircuitBuilder<F, D>, rhs: &Self) -> Self {
-        let a = self;
-        let b = rhs;
+        let a = self.coeffs();
+        let b = rhs.coeffs();
         let mut a0b0_coeffs: Vec<FqTarget<F, D>> = Vec::with_capacity(11);
         let mut a0b1_coeffs: Vec<FqTarget<F, D>> = Vec::with_capacity(11);
         let mut a1b0_coeffs: Vec<FqTarget<F, D>> = Vec::with_capacity(11);
         let mut a1b1_coeffs: Vec<FqTarget<F, D>> = Vec::with_capacity(11);
+        // up to 6 products are summed per slot, but no limb can overflow: add_nonnative
+        // witnesses sum = a + b - overflow * p with a boolean overflow and range checks the
+        // sum to u32 limbs and sum <= p. Every partial sum is thus reduced, whatever the
+        // number of terms, and the 8-limb layout holds any value below 2^256 > 2p
         for i in 0..6 {
             for j in 0..6 {
-                let coeff00 = a.coeffs[i].mul(builder, &b.coeffs[j]);
-                let coeff01 = a.coeffs[i].mul(builder, &b.coeffs[j + 6]);
-                let coeff10 = a.coeffs[i + 6].mul(builder, &b.coeffs[j]);
-                let coeff11 = a.coeffs[i + 6].mul(builder, &b.coeffs[j + 6]);
+                let coeff00 = a[i].mul(builder, &b[j]);
+                let coeff01 = a[i].mul(builder, &b[j + 6]);
+                let coeff10 = a[i + 6].mul(builder, &b[j]);
+                let coeff11 = a[i + 6].mul(builder, &b[j + 6]);
                 if i + j < a0b0_coeffs.len() {
                     a0b0_coeffs[i + j] = a0b0_coeffs[i + j].add(builder, &coeff00);
                     a0b1_coeffs[i + j] = a0b1_coeffs[i + j].add(builder, &coeff01);
@@ -155,229 +377,794 @@ impl<F: RichField + Extendable<D>, const D: usize> Fq12Target<F, D> {
             a0b1_plus_a1b0.push(a0b1_plus_a1b0_entry);
         }
 
-        let const_nine = FqTarget::constant(builder, Fq::from(9));
-        let mut out_coeffs: Vec<FqTarget<F, D>> = Vec::with_capacity(12);
-        for i in 0..6 {
-            if i < 5 {
-                // let coeff: Fq = a0b0_minus_a1b1[i] + Fq::from(9) * a0b0_minus_a1b1[i + 6]
-                //     - a0b1_plus_a1b0[i + 6];
-                let term0 = a0b0_minus_a1b1[i].clone();
-                let term1 = a0b0_minus_a1b1[i + 6].mul(builder, &const_nine);
-                let term2 = a0b1_plus_a1b0[i + 6].neg(builder);
-                let term0_plus_term1 = term0.add(builder, &term1);
-                let coeff = term0_plus_term1.add(builder, &term2);
-                out_coeffs.push(coeff);
-            } else {
-                out_coeffs.push(a0b0_minus_a1b1[i].clone());
-            }
-        }
-        for i in 0..6 {
-            if i < 5 {
-                // let coeff: Fq = a0b1_plus_a1b0[i]
-                //     + a0b0_minus_a1b1[i + 6]
-                //     + Fq::from(9) * a0b1_plus_a1b0[i + 6];
-                let term0 = a0b1_plus_a1b0[i].clone();
-                let term1 = a0b0_minus_a1b1[i + 6].clone();
-                let term2 = a0b1_plus_a1b0[i + 6].mul(builder, &const_nine);
-                let term0_plus_term1 = term0.add(builder, &term1);
-                let coeff = term0_plus_term1.add(builder, &term2);
-                out_coeffs.push(coeff);
-            } else {
-                out_coeffs.push(a0b1_plus_a1b0[i].clone());
-            }
-        }
-        Self {
-            coeffs: out_coeffs.try_into().unwrap(),
-        }
+        Self::reduce_products(builder, &a0b0_minus_a1b1, &a0b1_plus_a1b0)
     }
 
-    pub fn pow(&self, builder: &mut CircuitBuilder<F, D>, offset: &Self, exp_val: Target) -> Self {
-        let pow = Self::empty(builder);
-        builder.add_simple_generator(Fq12ExpGenerator::<F, D> {
-            x: self.clone(),
-            offset: offset.clone(),
-            exp_val,
-            output: pow.clone(),
-        });
-        pow
+    // same product as mul, computed on the tower Fq12 = Fq6[w] / (w^2 - v) with Karatsuba
+    // at every level: 3 Fq6 muls of 6 Fq2 muls of 3 Fq muls, i.e. 54 nonnative
+    // multiplications instead of mul's 144
+    pub fn mul_karatsuba(&self, builder: &mut CircuitBuilder<F, D>, rhs: &Self) -> Self {
+        let (a0, a1) = self.as_fq6_pair();
+        let (b0, b1) = rhs.as_fq6_pair();
+        let a0b0 = a0.mul_karatsuba(builder, &b0);
+        let a1b1 = a1.mul_karatsuba(builder, &b1);
+        let a1b1_v = a1b1.mul_by_nonresidue(builder);
+        let c0 = a0b0.add(builder, &a1b1_v);
+
+        let a0_plus_a1 = a0.add(builder, &a1);
+        let b0_plus_b1 = b0.add(builder, &b1);
+        let c1 = a0_plus_a1.mul_karatsuba(builder, &b0_plus_b1);
+        let c1 = c1.sub(builder, &a0b0);
+        let c1 = c1.sub(builder, &a1b1);
+        Self::from_fq6_pair(c0, c1)
     }
 
-    pub fn div(&self, builder: &mut CircuitBuilder<F, D>, other: &Self) -> Self {
-        let inv = other.inv(builder);
-        self.mul(builder, &inv)
+    // (c0, c1) with self = c0 + c1 w
+    pub fn as_fq6_pair(&self) -> (Fq6Target<F, D>, Fq6Target<F, D>) {
+        (self.c0.clone(), self.c1.clone())
     }
 
-    pub fn inv(&self, builder: &mut CircuitBuilder<F, D>) -> Self {
-        let inv = Self::empty(builder);
-        builder.add_simple_generator(Fq12InverseGenerator::<F, D> {
-            x: self.clone(),
-            inv: inv.clone(),
-        });
-        let one = Self::constant(builder, Fq12::ONE);
-        let x_mul_inv = self.mul(builder, &inv);
-        Self::connect(builder, &x_mul_inv, &one);
-        inv
+    pub fn from_fq6_pair(c0: Fq6Target<F, D>, c1: Fq6Target<F, D>) -> Self {
+        Self { c0, c1 }
     }
 
-    pub fn conjugate(&self, builder: &mut CircuitBuilder<F, D>) -> Self {
-        let mut coeffs = self.coeffs.clone();
-        coeffs[1] = coeffs[1].neg(builder);
-        coeffs[3] = coeffs[3].neg(builder);
-        coeffs[5] = coeffs[5].neg(builder);
-        coeffs[7] = coeffs[7].neg(builder);
-        coeffs[9] = coeffs[9].neg(builder);
-        coeffs[11] = coeffs[11].neg(builder);
-        Self { coeffs }
+    // a^2 with the same output as a.mul(a), but each cross term a_i * a_j (i != j) is
+    // computed once and doubled: 78 nonnative multiplications instead of 144
+    pub fn square(&self, builder: &mut CircuitBuilder<F, D>) -> Self {
+        let a = self.coeffs();
+        let mut a0a0: Vec<Option<FqTarget<F, D>>> = vec![None; 11];
+        let mut a1a1: Vec<Option<FqTarget<F, D>>> = vec![None; 11];
+        let mut a0a1: Vec<Option<FqTarget<F, D>>> = vec![None; 11];
+        let accumulate = |builder: &mut CircuitBuilder<F, D>,
+                          acc: &mut Option<FqTarget<F, D>>,
+                          term: FqTarget<F, D>| {
+            *acc = Some(match acc.take() {
+                Some(prev) => prev.add(builder, &term),
+                None => term,
+            });
+        };
+        for i in 0..6 {
+            for j in i..6 {
+                let mut coeff00 = a[i].mul(builder, &a[j]);
+                let mut coeff11 = a[i + 6].mul(builder, &a[j + 6]);
+                if i != j {
+                    coeff00 = coeff00.add(builder, &coeff00);
+                    coeff11 = coeff11.add(builder, &coeff11);
+                }
+                accumulate(builder, &mut a0a0[i + j], coeff00);
+                accumulate(builder, &mut a1a1[i + j], coeff11);
+            }
+            for j in 0..6 {
+                let coeff01 = a[i].mul(builder, &a[j + 6]);
+                accumulate(builder, &mut a0a1[i + j], coeff01);
+            }
+        }
+
+        let mut a0a0_minus_a1a1: Vec<FqTarget<F, D>> = Vec::with_capacity(11);
+        let mut double_a0a1: Vec<FqTarget<F, D>> = Vec::with_capacity(11);
+        for i in 0..11 {
+            let a0a0_i = a0a0[i].take().unwrap();
+            let a1a1_i = a1a1[i].take().unwrap();
+            let a0a1_i = a0a1[i].take().unwrap();
+            a0a0_minus_a1a1.push(a0a0_i.sub(builder, &a1a1_i));
+            double_a0a1.push(a0a1_i.add(builder, &a0a1_i));
+        }
+
+        Self::reduce_products(builder, &a0a0_minus_a1a1, &double_a0a1)
     }
 
-    pub fn conditional_mul(
-        &self,
+    // folds the degree <= 10 products back into the six Fq2 slots using
+    // w^6 = xi, see Fq2Target::mul_by_nonresidue
+    fn reduce_products(
         builder: &mut CircuitBuilder<F, D>,
-        x: &Self,
-        flag: &BoolTarget,
+        a0b0_minus_a1b1: &[FqTarget<F, D>],
+        a0b1_plus_a1b0: &[FqTarget<F, D>],
     ) -> Self {
-        let muled = self.mul(builder, x);
-        Self::select(builder, &muled, &self, flag)
+        let slot = |i: usize| Fq2Target {
+            coeffs: [a0b0_minus_a1b1[i].clone(), a0b1_plus_a1b0[i].clone()],
+        };
+        let slots = (0..6)
+            .map(|i| {
+                if i < 5 {
+                    let high = slot(i + 6).mul_by_nonresidue(builder);
+                    slot(i).add(builder, &high)
+                } else {
+                    slot(i)
+                }
+            })
+            .collect_vec();
+        Self::from_fq2_slots(slots.try_into().unwrap())
     }
 
-    pub fn serialize(
-        &self,
-        dst: &mut Vec<u8>,
-        common_data: &CommonCircuitData<F, D>,
-    ) -> Result<(), IoError> {
-        for fq in &self.coeffs {
-            fq.serialize(dst, common_data)?;
-        }
-
-        Ok(())
+    // slot k is the Fq2 coefficient of w^k: as v = w^2, that is c0.c(k / 2) for even k and
+    // c1.c(k / 2) for odd k
+    pub(crate) fn fq2_slots(&self) -> [Fq2Target<F, D>; 6] {
+        core::array::from_fn(|k| [&self.c0, &self.c1][k % 2].coeffs[k / 2].clone())
     }
 
-    pub fn deserialize(
-        src: &mut Buffer,
-        common_data: &CommonCircuitData<F, D>,
-    ) -> Result<Self, IoError> {
-        let coeffs = [0; 12]
-            .iter()
-            .map(|_| FqTarget::deserialize(src, common_data))
-            .collect::<Result<Vec<_>, _>>()?
-            .try_into()
-            .unwrap();
-
-        Ok(Self { coeffs })
+    pub(crate) fn from_fq2_slots(slots: [Fq2Target<F, D>; 6]) -> Self {
+        let [s0, s1, s2, s3, s4, s5] = slots;
+        Self {
+            c0: Fq6Target {
+                coeffs: [s0, s2, s4],
+            },
+            c1: Fq6Target {
+                coeffs: [s1, s3, s5],
+            },
+        }
     }
-}
 
-#[derive(Debug, Default)]
-pub struct Fq12InverseGenerator<F: RichField + Extendable<D>, const D: usize> {
-    x: Fq12Target<F, D>,
-    inv: Fq12Target<F, D>,
-}
+    // the six Fq2 coefficients in arkworks' tower order
+    // [c0.c0, c0.c1, c0.c2, c1.c0, c1.c1, c1.c2]
+    pub fn as_fq2_coeffs(&self) -> [Fq2Target<F, D>; 6] {
+        let [c00, c01, c02] = self.c0.coeffs.clone();
+        let [c10, c11, c12] = self.c1.coeffs.clone();
+        [c00, c01, c02, c10, c11, c12]
+    }
 
-impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F, D>
-    for Fq12InverseGenerator<F, D>
-{
-    fn dependencies(&self) -> Vec<Target> {
-        self.x
-            .coeffs
-            .iter()
-            .flat_map(|coeff| coeff.target.value.limbs.iter().map(|&l| l.0))
-            .collect_vec()
+    pub fn from_fq2_coeffs(coeffs: [Fq2Target<F, D>; 6]) -> Self {
+        let [c00, c01, c02, c10, c11, c12] = coeffs;
+        Self {
+            c0: Fq6Target {
+                coeffs: [c00, c01, c02],
+            },
+            c1: Fq6Target {
+                coeffs: [c10, c11, c12],
+            },
+        }
     }
 
-    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
-        let coeffs: Vec<Fq> = self
-            .x
-            .coeffs
-            .iter()
-            .map(|x| from_biguint_to_fq(witness.get_biguint_target(x.target.value.clone())))
-            .collect_vec();
-        let x = MyFq12 {
-            coeffs: coeffs.try_into().unwrap(),
-        };
-        let x: Fq12 = x.into();
-        let inv_x: Fq12 = x.inverse().unwrap();
-        let inv_x: MyFq12 = inv_x.into();
-        let inv_x_biguint: Vec<BigUint> = inv_x
-            .coeffs
-            .iter()
-            .cloned()
-            .map(|coeff| coeff.into())
+    // sums products of Fq2 slots, each tagged with the power of w it lands on (0..11).
+    // w^6 = 9 + u, so products landing on w^(k + 6) are folded into w^k
+    fn from_slot_products(
+        builder: &mut CircuitBuilder<F, D>,
+        products: Vec<(usize, Fq2Target<F, D>)>,
+    ) -> Self {
+        let mut low: Vec<Option<Fq2Target<F, D>>> = vec![None; 6];
+        let mut high: Vec<Option<Fq2Target<F, D>>> = vec![None; 6];
+        for (k, term) in products {
+            let acc = if k < 6 { &mut low[k] } else { &mut high[k - 6] };
+            *acc = Some(match acc.take() {
+                Some(prev) => prev.add(builder, &term),
+                None => term,
+            });
+        }
+        let slots = low
+            .into_iter()
+            .zip(high)
+            .map(|(l, h)| {
+                let h_xi = h.map(|h| h.mul_by_nonresidue(builder));
+                match (l, h_xi) {
+                    (Some(l), Some(h_xi)) => l.add(builder, &h_xi),
+                    (Some(x), None) | (None, Some(x)) => x,
+                    (None, None) => Fq2Target::constant(builder, Fq2::ZERO),
+                }
+            })
             .collect_vec();
+        Self::from_fq2_slots(slots.try_into().unwrap())
+    }
 
-        for i in 0..12 {
-            out_buffer.set_biguint_target(&self.inv.coeffs[i].target.value, &inv_x_biguint[i]);
+    // multiplies by the sparse element c0 + c3 w + c4 w^3, which is arkworks'
+    // Fq12::new(Fq6::new(c0, 0, 0), Fq6::new(c3, c4, 0)) as produced by the line functions
+    pub fn mul_by_034(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        c0: &Fq2Target<F, D>,
+        c3: &Fq2Target<F, D>,
+        c4: &Fq2Target<F, D>,
+    ) -> Self {
+        let a = self.fq2_slots();
+        let sparse = [(0, c0), (1, c3), (3, c4)];
+        let mut products = vec![];
+        for (i, a_i) in a.iter().enumerate() {
+            for (j, b_j) in sparse.iter() {
+                products.push((i + j, a_i.mul(builder, b_j)));
+            }
         }
+        Self::from_slot_products(builder, products)
     }
 
-    fn id(&self) -> std::string::String {
-        "Fq12InverseGenerator".to_string()
+    // multiplies by a constant. Zero Fq2 slots of c are skipped and slots equal to one
+    // need no multiplication, so sparse constants cost much less than mul(constant(c))
+    pub fn mul_constant(&self, builder: &mut CircuitBuilder<F, D>, c: Fq12) -> Self {
+        let a = self.fq2_slots();
+        let c: MyFq12 = c.into();
+        let mut products = vec![];
+        for (i, a_i) in a.iter().enumerate() {
+            for j in 0..6 {
+                let c_j = Fq2::new(c.coeffs[j], c.coeffs[j + 6]);
+                if c_j.is_zero() {
+                    continue;
+                }
+                let term = if c_j == Fq2::ONE {
+                    a_i.clone()
+                } else {
+                    a_i.mul_const(builder, &c_j)
+                };
+                products.push((i + j, term));
+            }
+        }
+        Self::from_slot_products(builder, products)
     }
 
-    fn serialize(
-        &self,
-        dst: &mut Vec<u8>,
-        common_data: &CommonCircuitData<F, D>,
-    ) -> Result<(), IoError> {
-        self.x.serialize(dst, common_data)?;
-        self.inv.serialize(dst, common_data)
+    // multiplies the factors pairwise in a balanced tree, an empty slice gives one
+    pub fn product(builder: &mut CircuitBuilder<F, D>, factors: &[Self]) -> Self {
+        if factors.is_empty() {
+            return Self::one(builder);
+        }
+        let mut layer = factors.to_vec();
+        while layer.len() > 1 {
+            layer = layer
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => a.mul(builder, b),
+                    [a] => a.clone(),
+                    _ => unreachable!(),
+                })
+                .collect_vec();
+        }
+        layer.pop().unwrap()
     }
 
-    fn deserialize(
-        src: &mut Buffer,
-        common_data: &CommonCircuitData<F, D>,
-    ) -> Result<Self, IoError> {
-        let x = Fq12Target::deserialize(src, common_data)?;
-        let inv = Fq12Target::deserialize(src, common_data)?;
+    pub fn assert_eq_product(
+        builder: &mut CircuitBuilder<F, D>,
+        factors: &[Self],
+        expected: &Self,
+    ) {
+        let product = Self::product(builder, factors);
+        Self::connect(builder, &product, expected);
+    }
 
-        Ok(Self { x, inv })
+    // Granger-Scott squaring, same formulas as arkworks' Fp12 cyclotomic_square.
+    // Only correct if self lies in the cyclotomic subgroup (self^(p^4 - p^2 + 1) = 1 after
+    // the easy part of final exponentiation, e.g. conj(x) / x); the result is unconstrained
+    // garbage otherwise. 6 Fq2 multiplications instead of the 78 Fq ones of square
+    pub fn cyclotomic_square(&self, builder: &mut CircuitBuilder<F, D>) -> Self {
+        let [r0, r4, r3, r2, r1, r5] = self.as_fq2_coeffs();
+        // (a + b y)^2 over Fq4 = Fq2[y] / (y^2 - xi), returned as (even, odd)
+        let fq4_square =
+            |builder: &mut CircuitBuilder<F, D>, a: &Fq2Target<F, D>, b: &Fq2Target<F, D>| {
+                let ab = a.mul(builder, b);
+                let a_plus_b = a.add(builder, b);
+                let b_xi = b.mul_by_nonresidue(builder);
+                let b_xi_plus_a = b_xi.add(builder, a);
+                let ab_xi = ab.mul_by_nonresidue(builder);
+                let even = a_plus_b.mul(builder, &b_xi_plus_a);
+                let even = even.sub(builder, &ab);
+                let even = even.sub(builder, &ab_xi);
+                let odd = ab.add(builder, &ab);
+                (even, odd)
+            };
+        let (t0, t1) = fq4_square(builder, &r0, &r1);
+        let (t2, t3) = fq4_square(builder, &r2, &r3);
+        let (t4, t5) = fq4_square(builder, &r4, &r5);
+        // 3t - 2r and 3t + 2r
+        let sub_triple =
+            |builder: &mut CircuitBuilder<F, D>, t: &Fq2Target<F, D>, r: &Fq2Target<F, D>| {
+                let z = t.sub(builder, r);
+                let z = z.add(builder, &z);
+                z.add(builder, t)
+            };
+        let add_triple =
+            |builder: &mut CircuitBuilder<F, D>, t: &Fq2Target<F, D>, r: &Fq2Target<F, D>| {
+                let z = t.add(builder, r);
+                let z = z.add(builder, &z);
+                z.add(builder, t)
+            };
+        let z0 = sub_triple(builder, &t0, &r0);
+        let z1 = add_triple(builder, &t1, &r1);
+        let t5_xi = t5.mul_by_nonresidue(builder);
+        let z2 = add_triple(builder, &t5_xi, &r2);
+        let z3 = sub_triple(builder, &t4, &r3);
+        let z4 = sub_triple(builder, &t2, &r4);
+        let z5 = add_triple(builder, &t3, &r5);
+        Self::from_fq2_coeffs([z0, z4, z3, z2, z1, z5])
     }
-}
 
-#[derive(Debug, Default)]
-pub struct Fq12ExpGenerator<F: RichField + Extendable<D>, const D: usize> {
-    pub x: Fq12Target<F, D>,
-    pub offset: Fq12Target<F, D>,
-    pub exp_val: Target,
-    pub output: Fq12Target<F, D>,
-}
+    // square-and-multiply over the bits of a constant exponent, fully constrained
+    pub fn pow_constant(&self, builder: &mut CircuitBuilder<F, D>, exp: &BigUint) -> Self {
+        if exp.is_zero() {
+            return Self::one(builder);
+        }
+        let mut res = self.clone();
+        for i in (0..exp.bits() - 1).rev() {
+            res = res.square(builder);
+            if exp.bit(i) {
+                res = res.mul(builder, self);
+            }
+        }
+        res
+    }
 
-impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F, D>
-    for Fq12ExpGenerator<F, D>
-{
-    fn dependencies(&self) -> Vec<Target> {
-        self.x
-            .coeffs
-            .iter()
-            .flat_map(|coeff| coeff.target.value.limbs.iter().map(|&l| l.0))
-            .collect_vec()
+    // asserts self^exp = 1, i.e. the order of self divides exp
+    pub fn assert_pow_is_one(&self, builder: &mut CircuitBuilder<F, D>, exp: &BigUint) {
+        let res = self.pow_constant(builder, exp);
+        let one = Self::one(builder);
+        Self::connect(builder, &res, &one);
     }
 
-    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
-        let x_coeffs: [Fq; 12] = self
-            .x
-            .clone()
-            .coeffs
-            .map(|x| get_u256_biguint(witness, &x.to_vec()).into());
-        let x: Fq12 = MyFq12 { coeffs: x_coeffs }.into();
-        let offset_coeffs = self
-            .offset
-            .clone()
-            .coeffs
-            .map(|x| get_u256_biguint(witness, &x.to_vec()).into());
-        let offset: Fq12 = MyFq12 {
-            coeffs: offset_coeffs,
-        }
-        .into();
-        let exp_val = witness.get_target(self.exp_val).to_canonical_u64();
-        let output = offset * x.pow(&[exp_val]);
-        self.output.set_witness(out_buffer, &output);
+    // asserts self lies in the order r subgroup GT
+    pub fn assert_rth_root_of_unity(&self, builder: &mut CircuitBuilder<F, D>) {
+        let r: BigUint = Fr::MODULUS.into();
+        self.assert_pow_is_one(builder, &r);
     }
 
-    fn id(&self) -> String {
-        "Fq12ExpGenerator".to_string()
+    // self^exp for exp given as little-endian bits, by square-and-multiply from the most
+    // significant bit. Every step is a constrained square and conditional_mul, so the
+    // output is sound without any generator
+    pub fn pow_constrained(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        exp_bits: &[BoolTarget],
+    ) -> Self {
+        let mut res = Self::one(builder);
+        for bit in exp_bits.iter().rev() {
+            res = res.square_and_conditional_mul(builder, self, bit);
+        }
+        res
     }
 
-    fn serialize(
+    // same output as pow_constrained with a signed window recoding, the fixed-window form
+    // of w-NAF (Joye and Tunstall, "Exponent recoding and regular exponentiation
+    // algorithms"): every window of w bits is one odd digit in +-{1, 3, ..., 2^w - 1}, so
+    // each window is w squarings and exactly one mul by a table entry picked with
+    // random_access. The table holds self^(+-1), ..., self^(+-(2^w - 1)), computed from the
+    // odd powers self, self^3, ... and the inverse. For 254 bits and w = 4 that is 63 muls
+    // (+ the table and one to correct an even exponent) against 254 conditional_muls.
+    // self must be nonzero, see cyclotomic_pow_windowed for GT
+    pub fn pow_windowed(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        exp_bits: &[BoolTarget],
+        window: usize,
+    ) -> Self {
+        self.pow_signed_windows(builder, exp_bits, window, false)
+    }
+
+    // pow_windowed for self in the cyclotomic subgroup: inverses are conjugates and the
+    // squarings are cyclotomic_square, so negative digits cost nothing extra
+    pub fn cyclotomic_pow_windowed(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        exp_bits: &[BoolTarget],
+        window: usize,
+    ) -> Self {
+        self.pow_signed_windows(builder, exp_bits, window, true)
+    }
+
+    // k | 1 with bits b_i is sum_(i < N - 1) (2 b_(i + 1) - 1) 2^i + 2^(N - 1) for N >= the
+    // bit length, i.e. N digits in {-1, 1} that don't depend on b_0. A window of w such
+    // digits is 2u - (2^w - 1) for u the little-endian value of the shifted bits, so table[u]
+    // = self^(2u - (2^w - 1)) is picked by u directly. For an even k the result is
+    // multiplied by self^-1 at the end
+    fn pow_signed_windows(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        exp_bits: &[BoolTarget],
+        window: usize,
+        cyclotomic: bool,
+    ) -> Self {
+        assert!(window > 0, "window must be at least one bit");
+        if exp_bits.is_empty() {
+            return Self::one(builder);
+        }
+        let square = |x: &Self, builder: &mut CircuitBuilder<F, D>| {
+            if cyclotomic {
+                x.cyclotomic_square(builder)
+            } else {
+                x.square(builder)
+            }
+        };
+        let inverse = |x: &Self, builder: &mut CircuitBuilder<F, D>| {
+            if cyclotomic {
+                x.cyclotomic_inverse(builder)
+            } else {
+                x.inv(builder)
+            }
+        };
+
+        // x^1, x^3, ..., x^(2^w - 1)
+        let half = 1 << (window - 1);
+        let odd_powers = |x: &Self, builder: &mut CircuitBuilder<F, D>| {
+            let mut powers = vec![x.clone()];
+            if half > 1 {
+                let x_squared = square(x, builder);
+                while powers.len() < half {
+                    let next = powers.last().unwrap().mul(builder, &x_squared);
+                    powers.push(next);
+                }
+            }
+            powers
+        };
+        let positive = odd_powers(self, builder);
+        let self_inv = inverse(self, builder);
+        // conjugating the positive powers is free, a general element pays for a second run
+        // over the one inverse
+        let mut table = if cyclotomic {
+            positive
+                .iter()
+                .map(|x| x.cyclotomic_inverse(builder))
+                .collect_vec()
+        } else {
+            odd_powers(&self_inv, builder)
+        };
+        table.reverse();
+        table.extend(positive);
+
+        let num_digits = exp_bits.len().div_ceil(window) * window;
+        let mut shifted = (1..num_digits)
+            .map(|i| exp_bits.get(i).copied().unwrap_or_else(|| builder._false()))
+            .collect_vec();
+        shifted.push(builder._true());
+
+        let mut res: Option<Self> = None;
+        for chunk in shifted.chunks(window).rev() {
+            let digit = builder.le_sum(chunk.iter());
+            let entry = Self::random_access(builder, digit, &table);
+            res = Some(match res {
+                Some(mut acc) => {
+                    for _ in 0..window {
+                        acc = square(&acc, builder);
+                    }
+                    acc.mul(builder, &entry)
+                }
+                None => entry,
+            });
+        }
+        let is_even = builder.not(exp_bits[0]);
+        res.unwrap().conditional_mul(builder, &self_inv, &is_even)
+    }
+
+    // offset * self^exp for an exponent of any width (e.g. a full 254-bit scalar), sound:
+    // pow_constrained over the 32 * limbs bits of exp, so a wide exponent pays a square and
+    // multiply per bit. See pow_unconstrained for a witness-only hint
+    pub fn pow(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        offset: &Self,
+        exp: &BigUintTarget,
+    ) -> Self {
+        let bits = exp
+            .limbs
+            .iter()
+            .flat_map(|limb| builder.split_le(limb.0, 32))
+            .collect_vec();
+        let res = self.pow_constrained(builder, &bits);
+        offset.mul(builder, &res)
+    }
+
+    // offset * self^exp as a bare witness written by Fq12ExpGenerator.
+    // WARNING: nothing constrains the output, a malicious prover can put any value there.
+    // Only for witness-only use, or when the caller checks the result itself; pow is the
+    // constrained version
+    pub fn pow_unconstrained(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        offset: &Self,
+        exp: &BigUintTarget,
+    ) -> Self {
+        let pow = Self::empty(builder);
+        builder.add_simple_generator(Fq12ExpGenerator::<F, D> {
+            x: self.clone(),
+            offset: offset.clone(),
+            exp: exp.clone(),
+            output: pow.clone(),
+        });
+        pow
+    }
+
+    // offset * self^exp, or offset * self^(-exp) when is_negative. The base is inverted
+    // before pow, so the exponent bits are used as they are; the inverse is taken of one when
+    // is_negative is false, so a zero self is only rejected for a negative exponent. For
+    // self in the cyclotomic subgroup, selecting cyclotomic_inverse is cheaper
+    pub fn pow_signed(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        offset: &Self,
+        exp: &BigUintTarget,
+        is_negative: &BoolTarget,
+    ) -> Self {
+        let one = Self::one(builder);
+        let to_invert = Self::select(builder, self, &one, is_negative);
+        let inv = to_invert.inv(builder);
+        let base = Self::select(builder, &inv, self, is_negative);
+        base.pow(builder, offset, exp)
+    }
+
+    pub fn div(&self, builder: &mut CircuitBuilder<F, D>, other: &Self) -> Self {
+        let inv = other.inv(builder);
+        self.mul(builder, &inv)
+    }
+
+    pub fn assert_nonzero(&self, builder: &mut CircuitBuilder<F, D>) {
+        let zero = Self::zero(builder);
+        let is_zero = self.is_equal(builder, &zero);
+        builder.assert_zero(is_zero.target);
+    }
+
+    // self * inv = 1 is checked with assert_equal, so a zero self fails witness generation
+    // with "Fq12 inverse of zero" rather than an anonymous copy constraint conflict: the
+    // generator writes the correct inverse for any nonzero self
+    pub fn inv(&self, builder: &mut CircuitBuilder<F, D>) -> Self {
+        self.inv_labelled(builder, "Fq12 inverse of zero")
+    }
+
+    // inv, failing with label instead when self is zero
+    pub(crate) fn inv_labelled(&self, builder: &mut CircuitBuilder<F, D>, label: &str) -> Self {
+        let inv = Self::empty(builder);
+        builder.add_simple_generator(Fq12InverseGenerator::<F, D> {
+            x: self.clone(),
+            inv: inv.clone(),
+        });
+        let one = Self::one(builder);
+        let x_mul_inv = self.mul(builder, &inv);
+        x_mul_inv.assert_equal(builder, &one, label);
+        inv
+    }
+
+    // the inverse as a bare witness: only the generator, no self * inv = 1 check.
+    // WARNING: nothing constrains the output, a malicious prover can put any value there.
+    // Only sound when the caller constrains it afterwards, e.g. a product with self that
+    // is already connected to one elsewhere in the circuit
+    pub fn inv_unchecked(&self, builder: &mut CircuitBuilder<F, D>) -> Self {
+        let inv = Self::empty(builder);
+        builder.add_simple_generator(Fq12InverseGenerator::<F, D> {
+            x: self.clone(),
+            inv: inv.clone(),
+        });
+        inv
+    }
+
+    // (inverse, is_invertible) without failing on zero: self * inverse is constrained to be
+    // 1 - is_zero, so the flag can't be faked either way, and the inverse of zero is selected
+    // to be zero since nothing else constrains it
+    pub fn try_inv(&self, builder: &mut CircuitBuilder<F, D>) -> (Self, BoolTarget) {
+        let inv = Self::empty(builder);
+        builder.add_simple_generator(Fq12InverseGenerator::<F, D> {
+            x: self.clone(),
+            inv: inv.clone(),
+        });
+        let zero = Self::zero(builder);
+        let one = Self::one(builder);
+        let is_zero = self.is_equal(builder, &zero);
+        let expected = Self::select(builder, &zero, &one, &is_zero);
+        let x_mul_inv = self.mul(builder, &inv);
+        Self::connect(builder, &x_mul_inv, &expected);
+        let inv = Self::select(builder, &zero, &inv, &is_zero);
+        (inv, builder.not(is_zero))
+    }
+
+    // inverses of all xs with Montgomery's trick: the prefix products x_0 * ... * x_i, one
+    // inv of the full product, then inv(x_i) = inv(x_0 * ... * x_i) * (x_0 * ... * x_(i-1)).
+    // One inversion and 3n - 2 muls instead of n inversions. Every x must be nonzero
+    pub fn batch_inv(builder: &mut CircuitBuilder<F, D>, xs: &[Self]) -> Vec<Self> {
+        if xs.is_empty() {
+            return vec![];
+        }
+        let mut prefixes = vec![xs[0].clone()];
+        for x in xs.iter().skip(1) {
+            let prefix = prefixes.last().unwrap().mul(builder, x);
+            prefixes.push(prefix);
+        }
+        let mut acc = prefixes.last().unwrap().inv(builder);
+        let mut invs = vec![Self::default(); xs.len()];
+        for i in (1..xs.len()).rev() {
+            invs[i] = acc.mul(builder, &prefixes[i - 1]);
+            acc = acc.mul(builder, &xs[i]);
+        }
+        invs[0] = acc;
+        invs
+    }
+
+    // c0 + c1 w -> c0 - c1 w
+    pub fn conjugate(&self, builder: &mut CircuitBuilder<F, D>) -> Self {
+        Self {
+            c0: self.c0.clone(),
+            c1: self.c1.neg(builder),
+        }
+    }
+
+    // inverse of an element of the cyclotomic subgroup, where x^(-1) = x^(p^6) = conj(x):
+    // only six Fq negations, no inverse generator. Unchecked: for any other x the result is
+    // conj(x), not the inverse. Use inv, or assert_conjugate_is_inverse first, if unsure
+    pub fn cyclotomic_inverse(&self, builder: &mut CircuitBuilder<F, D>) -> Self {
+        self.conjugate(builder)
+    }
+
+    // for unitary x (x * conj(x) = 1, e.g. any element of the cyclotomic subgroup) the
+    // inverse is the conjugate. Asserting x * conj(x) = 1 is the same as conj(x) = inv(x)
+    // but needs no inverse generator
+    pub fn assert_conjugate_is_inverse(&self, builder: &mut CircuitBuilder<F, D>) {
+        let conj = self.conjugate(builder);
+        let x_mul_conj = self.mul(builder, &conj);
+        let one = Self::one(builder);
+        Self::connect(builder, &x_mul_conj, &one);
+    }
+
+    // x -> x^(p^power), applied per Fq2 coefficient of w^k as conj^power(c_k) * gamma_k.
+    // Linear: only constant multiplications, no inverse generators
+    pub fn frobenius_map(&self, builder: &mut CircuitBuilder<F, D>, power: usize) -> Self {
+        let gammas = frobenius_coeffs(power);
+        let mut slots = self.fq2_slots();
+        for (c_k, gamma) in slots.iter_mut().zip(gammas.iter()) {
+            if power % 2 == 1 {
+                *c_k = c_k.conjugate(builder);
+            }
+            if *gamma != Fq2::ONE {
+                *c_k = c_k.mul_const(builder, gamma);
+            }
+        }
+        Self::from_fq2_slots(slots)
+    }
+
+    // phi applied count times. phi^count is itself the map x -> x^(p^count) with closed-form
+    // constants, so this costs one frobenius_map pass whatever count is. phi^12 = id
+    pub fn repeated_frobenius(&self, builder: &mut CircuitBuilder<F, D>, count: usize) -> Self {
+        self.frobenius_map(builder, count % 12)
+    }
+
+    pub fn conditional_mul(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        x: &Self,
+        flag: &BoolTarget,
+    ) -> Self {
+        let muled = self.mul(builder, x);
+        Self::select(builder, &muled, &self, flag)
+    }
+
+    // self^2 * base if bit else self^2: one step of an MSB-first square-and-multiply
+    // ladder. The mul is paid whatever bit is
+    pub fn square_and_conditional_mul(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        base: &Self,
+        bit: &BoolTarget,
+    ) -> Self {
+        self.square(builder).conditional_mul(builder, base, bit)
+    }
+
+    // framed as [SERIALIZATION_VERSION, 12, coefficients...] so that a buffer written with
+    // another FqTarget format fails to deserialize instead of being misparsed. deserialize
+    // still reads the unframed layout of older versions
+    pub fn serialize(
+        &self,
+        dst: &mut Vec<u8>,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Result<(), IoError> {
+        dst.write_u8(Self::SERIALIZATION_VERSION)?;
+        dst.write_u8(12)?;
+        self.serialize_unframed(dst, common_data)
+    }
+
+    // targets are wire and virtual target indices, so this reads back the very targets that
+    // were serialized and allocates nothing: a generator such as Fq12ExpGenerator restored
+    // with CircuitData::from_bytes writes into the same targets as the original circuit.
+    // A buffer that doesn't start with a frame is read as unframed: there the first
+    // coefficient starts with its limb count as a little-endian usize, whose second byte
+    // is 0 and never 12, so the two layouts can't be confused. A frame of another version
+    // is an error
+    pub fn deserialize(
+        src: &mut Buffer,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Result<Self, IoError> {
+        if let [version, 12, ..] = src.unread_bytes() {
+            if *version != Self::SERIALIZATION_VERSION {
+                return Err(IoError);
+            }
+            src.read_u8()?;
+            src.read_u8()?;
+        }
+        Self::deserialize_unframed(src, common_data)
+    }
+
+    // the 12 coefficients back to back, as written before the framing was introduced.
+    // Only to write buffers for older versions: nothing checks the layout
+    pub fn serialize_unframed(
+        &self,
+        dst: &mut Vec<u8>,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Result<(), IoError> {
+        for fq in &self.coeffs() {
+            fq.serialize(dst, common_data)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn deserialize_unframed(
+        src: &mut Buffer,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Result<Self, IoError> {
+        let coeffs = [0; 12]
+            .iter()
+            .map(|_| FqTarget::deserialize(src, common_data))
+            .collect::<Result<Vec<_>, _>>()?
+            .try_into()
+            .unwrap();
+
+        Ok(Self::from_coeffs(coeffs))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Fq12InverseGenerator<F: RichField + Extendable<D>, const D: usize> {
+    x: Fq12Target<F, D>,
+    inv: Fq12Target<F, D>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F, D>
+    for Fq12InverseGenerator<F, D>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        self.x.to_vec()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let x = self.x.get_witness(witness);
+        // zero has no inverse. Writing zero defers the failure to the caller's check: the
+        // labelled assert_equal in inv, or the is_zero flag in try_inv
+        let inv_x: Fq12 = x.inverse().unwrap_or(Fq12::ZERO);
+        self.inv.set_witness(out_buffer, &inv_x);
+    }
+
+    fn id(&self) -> std::string::String {
+        "Fq12InverseGenerator".to_string()
+    }
+
+    fn serialize(
+        &self,
+        dst: &mut Vec<u8>,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Result<(), IoError> {
+        self.x.serialize(dst, common_data)?;
+        self.inv.serialize(dst, common_data)
+    }
+
+    fn deserialize(
+        src: &mut Buffer,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Result<Self, IoError> {
+        let x = Fq12Target::deserialize(src, common_data)?;
+        let inv = Fq12Target::deserialize(src, common_data)?;
+
+        Ok(Self { x, inv })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Fq12ExpGenerator<F: RichField + Extendable<D>, const D: usize> {
+    pub x: Fq12Target<F, D>,
+    pub offset: Fq12Target<F, D>,
+    pub exp: BigUintTarget,
+    pub output: Fq12Target<F, D>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F, D>
+    for Fq12ExpGenerator<F, D>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        self.x
+            .to_vec()
+            .into_iter()
+            .chain(self.offset.to_vec())
+            .chain(self.exp.limbs.iter().map(|&l| l.0))
+            .collect_vec()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let x = self.x.get_witness(witness);
+        let offset = self.offset.get_witness(witness);
+        let exp = witness.get_biguint_target(self.exp.clone());
+        let output = offset * x.pow(exp.to_u64_digits());
+        self.output.set_witness(out_buffer, &output);
+    }
+
+    fn id(&self) -> String {
+        "Fq12ExpGenerator".to_string()
+    }
+
+    fn serialize(
         &self,
         dst: &mut Vec<u8>,
         common_data: &CommonCircuitData<F, D>,
@@ -385,95 +1172,1622 @@ impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F, D>
         self.x.serialize(dst, common_data)?;
         self.offset.serialize(dst, common_data)?;
         self.output.serialize(dst, common_data)?;
-        dst.write_target(self.exp_val)
+        dst.write_target_vec(&self.exp.limbs.iter().map(|l| l.0).collect_vec())
+    }
+
+    fn deserialize(
+        src: &mut Buffer,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> plonky2::util::serialization::IoResult<Self>
+    where
+        Self: Sized,
+    {
+        let x = Fq12Target::deserialize(src, common_data)?;
+        let offset = Fq12Target::deserialize(src, common_data)?;
+        let output = Fq12Target::deserialize(src, common_data)?;
+        let exp = BigUintTarget {
+            limbs: src.read_target_vec()?.into_iter().map(U32Target).collect(),
+        };
+
+        Ok(Self {
+            x,
+            offset,
+            output,
+            exp,
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Fq12AssertEqualGenerator {
+    lhs: Vec<Target>,
+    rhs: Vec<Target>,
+    diffs: Vec<Target>,
+    label: String,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F, D>
+    for Fq12AssertEqualGenerator
+{
+    fn dependencies(&self) -> Vec<Target> {
+        self.lhs.iter().chain(self.rhs.iter()).copied().collect_vec()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let num_limbs = FqTarget::<F, D>::NUM_LIMBS;
+        let limbs_differ = |j: usize| {
+            witness.get_target(self.lhs[j]) != witness.get_target(self.rhs[j])
+        };
+        let differing = (0..12)
+            .filter(|i| (i * num_limbs..(i + 1) * num_limbs).any(limbs_differ))
+            .collect_vec();
+        if !differing.is_empty() {
+            panic!("{}: Fq12 values differ at coefficients {differing:?}", self.label);
+        }
+        for diff in self.diffs.iter() {
+            out_buffer.set_target(*diff, F::ZERO);
+        }
+    }
+
+    fn id(&self) -> std::string::String {
+        "Fq12AssertEqualGenerator".to_string()
+    }
+
+    fn serialize(
+        &self,
+        dst: &mut Vec<u8>,
+        _common_data: &CommonCircuitData<F, D>,
+    ) -> Result<(), IoError> {
+        dst.write_target_vec(&self.lhs)?;
+        dst.write_target_vec(&self.rhs)?;
+        dst.write_target_vec(&self.diffs)?;
+        dst.write_usize(self.label.len())?;
+        dst.write_all(self.label.as_bytes())
+    }
+
+    fn deserialize(
+        src: &mut Buffer,
+        _common_data: &CommonCircuitData<F, D>,
+    ) -> Result<Self, IoError> {
+        let lhs = src.read_target_vec()?;
+        let rhs = src.read_target_vec()?;
+        let diffs = src.read_target_vec()?;
+        let mut label = vec![0u8; src.read_usize()?];
+        src.read_exact(&mut label)?;
+        let label = String::from_utf8(label).map_err(|_| IoError)?;
+        Ok(Self {
+            lhs,
+            rhs,
+            diffs,
+            label,
+        })
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Fq12Target<F, D> {
+    // leading byte of the serialized form, bump it when the layout changes
+    pub const SERIALIZATION_VERSION: u8 = 1;
+
+    // 12 coefficients of FqTarget::NUM_LIMBS u32 limbs each
+    pub const NUM_PUBLIC_INPUTS: usize = 12 * FqTarget::<F, D>::NUM_LIMBS;
+
+    // limbs are padded to NUM_LIMBS per coefficient, so constants take the same layout
+    pub fn register_public_inputs(&self, builder: &mut CircuitBuilder<F, D>) {
+        self.register_as_public_inputs(builder);
+    }
+
+    // registers self and returns the registered targets. Layout: the 12 flat coefficients
+    // in MyFq12 order (coefficient i is that of w^(i % 6) u^(i / 6)), each as NUM_LIMBS
+    // little-endian u32 limbs, zero padded
+    pub fn register_as_public_inputs(&self, builder: &mut CircuitBuilder<F, D>) -> Vec<Target> {
+        let targets = self
+            .coeffs()
+            .iter()
+            .flat_map(|c| c.to_limbs(builder))
+            .collect_vec();
+        builder.register_public_inputs(&targets);
+        targets
+    }
+
+    // the native Fq12 from the NUM_PUBLIC_INPUTS values of a proof's public inputs in the
+    // register_as_public_inputs layout, e.g. &proof.public_inputs[..NUM_PUBLIC_INPUTS]
+    pub fn value_from_public_inputs(values: &[F]) -> Fq12 {
+        assert_eq!(
+            values.len(),
+            Self::NUM_PUBLIC_INPUTS,
+            "an Fq12 takes {} public inputs",
+            Self::NUM_PUBLIC_INPUTS
+        );
+        let coeffs = values
+            .chunks(FqTarget::<F, D>::NUM_LIMBS)
+            .map(|limbs| {
+                let digits = limbs
+                    .iter()
+                    .map(|l| u32::try_from(l.to_canonical_u64()).expect("limb is not a u32"))
+                    .collect_vec();
+                from_biguint_to_fq(BigUint::from_slice(&digits))
+            })
+            .collect_vec();
+        MyFq12 {
+            coeffs: coeffs.try_into().unwrap(),
+        }
+        .into()
+    }
+
+    // reads an Fq12 from the first NUM_PUBLIC_INPUTS targets, e.g. the public inputs of
+    // an inner proof
+    pub fn from_public_inputs(builder: &mut CircuitBuilder<F, D>, inputs: &[Target]) -> Self {
+        Self::from_vec(builder, &inputs[..Self::NUM_PUBLIC_INPUTS])
+    }
+
+    // connects self to an Fq12 an inner circuit exposed with register_public_inputs, given
+    // the first NUM_PUBLIC_INPUTS of that inner proof's public inputs. Typical use in the
+    // outer circuit: verify the inner proof, recompute the Fq12 from the outer's own inputs
+    // and call this on the recomputation with the proof target's public_inputs. Limbs are
+    // connected directly in the padded layout, so no range checks are added
+    pub fn connect_to_public_inputs(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        inner_pis: &[Target],
+    ) {
+        assert!(inner_pis.len() >= Self::NUM_PUBLIC_INPUTS);
+        let num_limbs = FqTarget::<F, D>::NUM_LIMBS;
+        for (c, pis) in self.coeffs().iter().zip(inner_pis.chunks(num_limbs)) {
+            let limbs = c.to_limbs(builder);
+            for (limb, pi) in limbs.iter().zip(pis) {
+                builder.connect(*limb, *pi);
+            }
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<Target> {
+        self.coeffs().iter().flat_map(|c| c.to_vec()).collect()
+    }
+
+    pub fn from_vec(builder: &mut CircuitBuilder<F, D>, input: &[Target]) -> Self {
+        let num_limbs = FqTarget::<F, D>::NUM_LIMBS;
+        assert_eq!(
+            input.len(),
+            12 * num_limbs,
+            "Fq12Target::from_vec expects 12 coefficients of {num_limbs} limbs"
+        );
+        let coeffs = input
+            .iter()
+            .cloned()
+            .chunks(num_limbs)
+            .into_iter()
+            .map(|chunk| FqTarget::from_vec(builder, &chunk.collect_vec()))
+            .collect_vec();
+        Self::new(coeffs)
+    }
+
+    // builds an Fq12 from one array of NUM_LIMBS little-endian u32 limbs per flat
+    // coefficient, in MyFq12 order. Each limb is range checked to 32 bits and each
+    // coefficient to be below p
+    pub fn from_limbs(builder: &mut CircuitBuilder<F, D>, limbs: &[[Target; 8]; 12]) -> Self {
+        let p_minus_one: BigUint = Fq::MODULUS.into();
+        let p_minus_one = builder.constant_biguint(&(p_minus_one - 1u32));
+        let coeffs = limbs.each_ref().map(|c| {
+            let c = FqTarget::from_vec(builder, c);
+            let is_reduced = builder.cmp_biguint(&c.target.value, &p_minus_one);
+            builder.assert_one(is_reduced.target);
+            c
+        });
+        Self::from_coeffs(coeffs)
+    }
+
+    // reads the 12 coefficients straight from their u32 limbs
+    pub fn get_witness<W: Witness<F>>(&self, witness: &W) -> Fq12 {
+        let coeffs = self
+            .coeffs()
+            .map(|c| from_biguint_to_fq(get_u256_biguint(witness, &c.to_vec())));
+        MyFq12 { coeffs }.into()
+    }
+
+    pub fn set_witness<W: WitnessWrite<F>>(&self, pw: &mut W, value: &Fq12) {
+        self.set_witness_my(pw, &value.clone().into());
+    }
+
+    // the flat coefficients as they are, for values already in MyFq12 order
+    pub fn set_witness_my<W: WitnessWrite<F>>(&self, pw: &mut W, value: &MyFq12) {
+        for (c_t, c) in self.coeffs().iter().zip(value.coeffs.iter()) {
+            c_t.set_witness(pw, c);
+        }
+    }
+
+    // sets the 12 flat coefficients from raw integers. Each must be reduced mod p: an
+    // out-of-range value panics here instead of being written as malformed limbs
+    pub fn set_witness_biguints<W: WitnessWrite<F>>(&self, pw: &mut W, values: &[BigUint; 12]) {
+        let modulus: BigUint = Fq::MODULUS.into();
+        for (i, (c_t, c)) in self.coeffs().iter().zip(values).enumerate() {
+            assert!(
+                c < &modulus,
+                "coefficient {i} of Fq12 witness is not reduced mod p: {c}"
+            );
+            c_t.set_witness(pw, &from_biguint_to_fq(c.clone()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use ark_bn254::{Fq, Fq12, Fq2, Fq6, Fr};
+    use ark_ff::{Field, PrimeField};
+    use ark_std::{UniformRand, Zero};
+    use num_bigint::BigUint;
+    use plonky2::{
+        field::{goldilocks_field::GoldilocksField, types::Field as Plonky2Field},
+        iop::{
+            generator::SimpleGenerator,
+            target::Target,
+            witness::{PartialWitness, WitnessWrite},
+        },
+        plonk::{
+            circuit_builder::CircuitBuilder,
+            circuit_data::{CircuitConfig, CircuitData},
+            config::PoseidonGoldilocksConfig,
+        },
+    };
+    use plonky2_crypto::u32::gadgets::arithmetic_u32::U32Target;
+    use plonky2_ecdsa::gadgets::{
+        biguint::{BigUintTarget, CircuitBuilderBiguint, WitnessBigUint},
+        nonnative::CircuitBuilderNonNative,
+    };
+    use rand::Rng;
+
+    use crate::{
+        curves::{BN254GateSerializer, BN254GeneratorSerializer},
+        fields::{
+            bn254base::Bn254Base, fq2_target::Fq2Target, fq6_target::Fq6Target,
+            fq_target::FqTarget,
+        },
+        utils::prove_and_verify,
+    };
+
+    use super::{
+        from_biguint_to_fq, Buffer, Fq12ExpGenerator, Fq12InverseGenerator, Fq12ShapeError,
+        Fq12Target, MyFq12,
+    };
+
+    type F = GoldilocksField;
+    type C = PoseidonGoldilocksConfig;
+    const D: usize = 2;
+
+    // r^(p^6 - 1) = conj(r) / r lies in the cyclotomic subgroup
+    fn random_cyclotomic<R: Rng>(rng: &mut R) -> Fq12 {
+        let r = Fq12::rand(rng);
+        let mut x = r;
+        x.conjugate_in_place();
+        x / r
+    }
+
+    #[test]
+    fn test_serialization_framing() {
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::constant(&mut builder, Fq12::rand(&mut rand::thread_rng()));
+        let data = builder.build::<C>();
+
+        let mut bytes = vec![];
+        x_t.serialize(&mut bytes, &data.common).unwrap();
+        assert_eq!(bytes[0], Fq12Target::<F, D>::SERIALIZATION_VERSION);
+        assert_eq!(bytes[1], 12);
+        let y_t = Fq12Target::deserialize(&mut Buffer::new(&bytes), &data.common).unwrap();
+        assert_eq!(x_t.to_vec(), y_t.to_vec());
+
+        let mut legacy = vec![];
+        x_t.serialize_unframed(&mut legacy, &data.common).unwrap();
+        assert_eq!(legacy, bytes[2..]);
+        let y_t =
+            Fq12Target::deserialize_unframed(&mut Buffer::new(&legacy), &data.common).unwrap();
+        assert_eq!(x_t.to_vec(), y_t.to_vec());
+        let y_t = Fq12Target::deserialize(&mut Buffer::new(&legacy), &data.common).unwrap();
+        assert_eq!(x_t.to_vec(), y_t.to_vec());
+
+        // a frame of another version is rejected outright. Without 12 in the second byte
+        // there is no frame and the bytes are read unframed, where the first limb count
+        // comes out far too large
+        let mut bad_version = bytes.clone();
+        bad_version[0] ^= 0xff;
+        let res = Fq12Target::<F, D>::deserialize(&mut Buffer::new(&bad_version), &data.common);
+        assert!(res.is_err());
+        let mut bad_count = bytes.clone();
+        bad_count[1] = 11;
+        let res = Fq12Target::<F, D>::deserialize(&mut Buffer::new(&bad_count), &data.common);
+        assert!(res.is_err());
+    }
+
+    // a generator serialized before the framing, i.e. with unframed Fq12Targets, still loads
+    #[test]
+    fn test_generator_deserializes_unframed() {
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::empty(&mut builder);
+        let inv_t = x_t.inv(&mut builder);
+        let data = builder.build::<C>();
+        let generator = Fq12InverseGenerator::<F, D> { x: x_t, inv: inv_t };
+
+        let mut legacy = vec![];
+        generator.x.serialize_unframed(&mut legacy, &data.common).unwrap();
+        generator.inv.serialize_unframed(&mut legacy, &data.common).unwrap();
+        let restored =
+            Fq12InverseGenerator::<F, D>::deserialize(&mut Buffer::new(&legacy), &data.common)
+                .unwrap();
+        assert_eq!(restored.x.to_vec(), generator.x.to_vec());
+        assert_eq!(restored.inv.to_vec(), generator.inv.to_vec());
+
+        let mut framed = vec![];
+        generator.serialize(&mut framed, &data.common).unwrap();
+        assert_ne!(framed, legacy);
+        let restored =
+            Fq12InverseGenerator::<F, D>::deserialize(&mut Buffer::new(&framed), &data.common)
+                .unwrap();
+        assert_eq!(restored.inv.to_vec(), generator.inv.to_vec());
+    }
+
+    // the pow and inverse generators survive a CircuitData round trip and still write
+    // into the targets of the original builder
+    #[test]
+    fn test_generators_serialization_roundtrip() {
+        let rng = &mut rand::thread_rng();
+        let x = Fq12::rand(rng);
+        let exp: u16 = rng.gen();
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::empty(&mut builder);
+        let offset = Fq12Target::one(&mut builder);
+        let exp_t = builder.constant_biguint(&BigUint::from(exp));
+        let pow_x_t = x_t.pow_unconstrained(&mut builder, &offset, &exp_t);
+        let pow_x_expected_t = Fq12Target::constant(&mut builder, x.pow([exp as u64]));
+        Fq12Target::connect(&mut builder, &pow_x_t, &pow_x_expected_t);
+        let inv_x_t = x_t.inv(&mut builder);
+        let inv_x_expected_t = Fq12Target::constant(&mut builder, x.inverse().unwrap());
+        Fq12Target::connect(&mut builder, &inv_x_t, &inv_x_expected_t);
+
+        let data = builder.build::<C>();
+        let gate_serializer = BN254GateSerializer {};
+        let generator_serializer = BN254GeneratorSerializer::<C, D> {
+            _phantom: PhantomData,
+        };
+        let bytes = data
+            .to_bytes(&gate_serializer, &generator_serializer)
+            .unwrap();
+        let data =
+            CircuitData::<F, C, D>::from_bytes(&bytes, &gate_serializer, &generator_serializer)
+                .unwrap();
+
+        let mut pw = PartialWitness::new();
+        x_t.set_witness(&mut pw, &x);
+        let proof = data.prove(pw).unwrap();
+        data.verify(proof).unwrap();
+    }
+
+    // an exponent witnessed across all 8 limbs (not a small constant) keeps every limb
+    // through the generator's length-prefixed target vec
+    #[test]
+    fn test_wide_exp_generator_serialization_roundtrip() {
+        let rng = &mut rand::thread_rng();
+        let x = Fq12::rand(rng);
+        let offset = Fq12::rand(rng);
+        let exp: BigUint = Fr::rand(rng).into();
+        let expected = offset * x.pow(exp.to_u64_digits());
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::empty(&mut builder);
+        let offset_t = Fq12Target::empty(&mut builder);
+        let exp_t = builder.add_virtual_biguint_target(8);
+        let pow_t = x_t.pow_unconstrained(&mut builder, &offset_t, &exp_t);
+        let expected_t = Fq12Target::constant(&mut builder, expected);
+        Fq12Target::connect(&mut builder, &pow_t, &expected_t);
+
+        let data = builder.build::<C>();
+        let generator = Fq12ExpGenerator::<F, D> {
+            x: x_t.clone(),
+            offset: offset_t.clone(),
+            exp: exp_t.clone(),
+            output: pow_t.clone(),
+        };
+        let mut bytes = vec![];
+        generator.serialize(&mut bytes, &data.common).unwrap();
+        let restored =
+            Fq12ExpGenerator::<F, D>::deserialize(&mut Buffer::new(&bytes), &data.common)
+                .unwrap();
+        assert_eq!(restored.exp.limbs, exp_t.limbs);
+        assert_eq!(restored.dependencies(), generator.dependencies());
+        assert_eq!(restored.output.to_vec(), pow_t.to_vec());
+
+        let gate_serializer = BN254GateSerializer {};
+        let generator_serializer = BN254GeneratorSerializer::<C, D> {
+            _phantom: PhantomData,
+        };
+        let bytes = data
+            .to_bytes(&gate_serializer, &generator_serializer)
+            .unwrap();
+        let data =
+            CircuitData::<F, C, D>::from_bytes(&bytes, &gate_serializer, &generator_serializer)
+                .unwrap();
+
+        let mut pw = PartialWitness::new();
+        x_t.set_witness(&mut pw, &x);
+        offset_t.set_witness(&mut pw, &offset);
+        pw.set_biguint_target(&exp_t, &exp);
+        let proof = data.prove(pw).unwrap();
+        data.verify(proof).unwrap();
+    }
+
+    #[test]
+    fn test_from_to_vec() {
+        let rng = &mut rand::thread_rng();
+        let a = Fq12::rand(rng);
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let a_t = Fq12Target::empty(&mut builder);
+
+        let a_vec = a_t.to_vec();
+        assert_eq!(a_vec.len(), 12 * FqTarget::<F, D>::NUM_LIMBS);
+        let restored_a_t = Fq12Target::from_vec(&mut builder, &a_vec);
+
+        Fq12Target::connect(&mut builder, &a_t, &restored_a_t);
+
+        let mut pw = PartialWitness::new();
+        a_t.set_witness(&mut pw, &a);
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw);
+    }
+
+    #[test]
+    fn test_from_limbs() {
+        let rng = &mut rand::thread_rng();
+        let a = Fq12::rand(rng);
+        let a_coeffs: MyFq12 = a.into();
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let limbs = a_coeffs.coeffs.map(|c| {
+            let c: BigUint = c.into();
+            let mut digits = c.to_u32_digits();
+            digits.resize(8, 0);
+            core::array::from_fn(|i| builder.constant(F::from_canonical_u32(digits[i])))
+        });
+        let a_t = Fq12Target::from_limbs(&mut builder, &limbs);
+        let expected_t = Fq12Target::constant(&mut builder, a);
+        Fq12Target::connect(&mut builder, &a_t, &expected_t);
+
+        prove_and_verify(builder, PartialWitness::new()).unwrap();
+    }
+
+    #[test]
+    fn test_from_biguint_to_fq() {
+        let rng = &mut rand::thread_rng();
+        let x = Fq::rand(rng);
+        let x_biguint: BigUint = x.into();
+        let converted_x = from_biguint_to_fq(x_biguint);
+        assert_eq!(x, converted_x);
+    }
+
+    #[test]
+    fn test_fq12_mul_circuit() {
+        let rng = &mut rand::thread_rng();
+        let a = Fq12::rand(rng);
+        let b = Fq12::rand(rng);
+        let c_expected = a * b;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let a_t = Fq12Target::constant(&mut builder, a);
+        let b_t = Fq12Target::constant(&mut builder, b);
+        let c_t = a_t.mul(&mut builder, &b_t);
+        let c_expected_t = Fq12Target::constant(&mut builder, c_expected);
+
+        Fq12Target::connect(&mut builder, &c_expected_t, &c_t);
+
+        let pw = PartialWitness::new();
+        prove_and_verify(builder, pw).unwrap();
+    }
+
+    // every flat coefficient is p - 1, so each accumulated slot of mul sums the largest
+    // possible products
+    #[test]
+    fn test_fq12_mul_max_coeffs() {
+        let minus_one = MyFq12 {
+            coeffs: [-Fq::ONE; 12],
+        };
+        let a: Fq12 = minus_one.into();
+        let expected = a * a;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let a_t = Fq12Target::empty(&mut builder);
+        let b_t = Fq12Target::empty(&mut builder);
+        let output_t = a_t.mul(&mut builder, &b_t);
+        let expected_t = Fq12Target::constant(&mut builder, expected);
+        Fq12Target::connect(&mut builder, &output_t, &expected_t);
+
+        let mut pw = PartialWitness::new();
+        a_t.set_witness(&mut pw, &a);
+        b_t.set_witness(&mut pw, &a);
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_get_witness() {
+        let rng = &mut rand::thread_rng();
+        let a = Fq12::rand(rng);
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_ecc_config());
+        let a_t = Fq12Target::empty(&mut builder);
+        let mut pw = PartialWitness::new();
+        a_t.set_witness(&mut pw, &a);
+        assert_eq!(a_t.get_witness(&pw), a);
+    }
+
+    #[test]
+    fn test_fq12_inv_circuit() {
+        let rng = &mut rand::thread_rng();
+        let x: Fq12 = Fq12::rand(rng);
+        let inv_x_expected = x.inverse().unwrap();
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::constant(&mut builder, x);
+        let inv_x_t = x_t.inv(&mut builder);
+        let inv_x_expected_t = Fq12Target::constant(&mut builder, inv_x_expected);
+
+        Fq12Target::connect(&mut builder, &inv_x_t, &inv_x_expected_t);
+
+        let pw = PartialWitness::new();
+        prove_and_verify(builder, pw).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "Fq12 inverse of zero")]
+    fn test_fq12_inv_of_zero_is_an_error() {
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::empty(&mut builder);
+        let _inv_t = x_t.inv(&mut builder);
+
+        let mut pw = PartialWitness::new();
+        x_t.set_witness(&mut pw, &Fq12::ZERO);
+        prove_and_verify(builder, pw).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "Fq12 inverse of zero")]
+    fn test_fq12_div_by_zero_is_an_error() {
+        let rng = &mut rand::thread_rng();
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::constant(&mut builder, Fq12::rand(rng));
+        let y_t = Fq12Target::empty(&mut builder);
+        let _div_t = x_t.div(&mut builder, &y_t);
+
+        let mut pw = PartialWitness::new();
+        y_t.set_witness(&mut pw, &Fq12::ZERO);
+        prove_and_verify(builder, pw).unwrap();
+    }
+
+    // the caller's own x * inv = 1 makes inv_unchecked sound, and inv would only repeat it
+    #[test]
+    fn test_inv_unchecked() {
+        let rng = &mut rand::thread_rng();
+        let x = Fq12::rand(rng);
+
+        let build = |checked: bool| {
+            let config = CircuitConfig::standard_ecc_config();
+            let mut builder = CircuitBuilder::<F, D>::new(config);
+            let x_t = Fq12Target::empty(&mut builder);
+            let inv_t = if checked {
+                x_t.inv(&mut builder)
+            } else {
+                x_t.inv_unchecked(&mut builder)
+            };
+            let one_t = Fq12Target::one(&mut builder);
+            let product_t = inv_t.mul(&mut builder, &x_t);
+            Fq12Target::connect(&mut builder, &product_t, &one_t);
+            (builder, x_t)
+        };
+
+        let (checked_builder, _) = build(true);
+        let (builder, x_t) = build(false);
+        assert!(builder.num_gates() < checked_builder.num_gates());
+
+        let mut pw = PartialWitness::new();
+        x_t.set_witness(&mut pw, &x);
+        prove_and_verify(builder, pw).unwrap();
+    }
+
+    fn check_try_inv(x: Fq12) {
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::empty(&mut builder);
+        let (inv_t, is_invertible_t) = x_t.try_inv(&mut builder);
+        let inv_expected_t = Fq12Target::constant(&mut builder, x.inverse().unwrap_or(Fq12::ZERO));
+        Fq12Target::connect(&mut builder, &inv_t, &inv_expected_t);
+        let is_invertible_expected_t = builder.constant_bool(!x.is_zero());
+        builder.connect(is_invertible_t.target, is_invertible_expected_t.target);
+
+        let mut pw = PartialWitness::new();
+        x_t.set_witness(&mut pw, &x);
+        prove_and_verify(builder, pw).unwrap();
+    }
+
+    #[test]
+    fn test_try_inv() {
+        let rng = &mut rand::thread_rng();
+        check_try_inv(Fq12::rand(rng));
+        check_try_inv(Fq12::ZERO);
+    }
+
+    #[test]
+    fn test_batch_inv() {
+        let rng = &mut rand::thread_rng();
+        let xs = (0..8).map(|_| Fq12::rand(rng)).collect::<Vec<_>>();
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let xs_t = xs
+            .iter()
+            .map(|_| Fq12Target::empty(&mut builder))
+            .collect::<Vec<_>>();
+        let invs_t = Fq12Target::batch_inv(&mut builder, &xs_t);
+        assert_eq!(invs_t.len(), xs.len());
+        let one_t = Fq12Target::constant(&mut builder, Fq12::ONE);
+        for (x_t, inv_t) in xs_t.iter().zip(invs_t.iter()) {
+            let expected_t = x_t.inv(&mut builder);
+            Fq12Target::connect(&mut builder, inv_t, &expected_t);
+            let product_t = x_t.mul(&mut builder, inv_t);
+            Fq12Target::connect(&mut builder, &product_t, &one_t);
+        }
+
+        let mut pw = PartialWitness::new();
+        for (x_t, x) in xs_t.iter().zip(xs.iter()) {
+            x_t.set_witness(&mut pw, x);
+        }
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    fn check_pow_signed(is_negative: bool) {
+        let rng = &mut rand::thread_rng();
+        let x = Fq12::rand(rng);
+        let offset = Fq12::rand(rng);
+        let exp: u64 = rng.gen();
+        let pow = x.pow([exp]);
+        let expected = if is_negative {
+            offset * pow.inverse().unwrap()
+        } else {
+            offset * pow
+        };
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::empty(&mut builder);
+        let offset_t = Fq12Target::constant(&mut builder, offset);
+        let exp_t = builder.constant_biguint(&BigUint::from(exp));
+        let is_negative_t = builder.add_virtual_bool_target_safe();
+        let output_t = x_t.pow_signed(&mut builder, &offset_t, &exp_t, &is_negative_t);
+        let expected_t = Fq12Target::constant(&mut builder, expected);
+        Fq12Target::connect(&mut builder, &output_t, &expected_t);
+
+        let mut pw = PartialWitness::new();
+        x_t.set_witness(&mut pw, &x);
+        pw.set_bool_target(is_negative_t, is_negative);
+        prove_and_verify(builder, pw).unwrap();
+    }
+
+    #[test]
+    fn test_pow_signed() {
+        check_pow_signed(false);
+        check_pow_signed(true);
+    }
+
+    #[test]
+    fn test_fq12_pow_circuit() {
+        let rng = &mut rand::thread_rng();
+        let x: Fq12 = Fq12::rand(rng);
+        let exp: u64 = rng.gen();
+        let pow_expected = x.pow([exp]);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::constant(&mut builder, x);
+        let offset = Fq12Target::constant(&mut builder, Fq12::ONE);
+        let exp_t = builder.constant_biguint(&BigUint::from(exp));
+        let pow_x_t = x_t.pow(&mut builder, &offset, &exp_t);
+        let pow_x_expected_t = Fq12Target::constant(&mut builder, pow_expected);
+
+        Fq12Target::connect(&mut builder, &pow_x_t, &pow_x_expected_t);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        dbg!(data.common.degree_bits());
+        let _proof = data.prove(pw);
+    }
+
+    #[test]
+    fn test_fq12_pow_full_width() {
+        let rng = &mut rand::thread_rng();
+        let x = Fq12::rand(rng);
+        let offset = Fq12::rand(rng);
+        let exp: BigUint = Fr::rand(rng).into();
+        let expected = offset * x.pow(exp.to_u64_digits());
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::empty(&mut builder);
+        let offset_t = Fq12Target::empty(&mut builder);
+        let exp_t = builder.add_virtual_biguint_target(8);
+        let pow_t = x_t.pow(&mut builder, &offset_t, &exp_t);
+        let expected_t = Fq12Target::constant(&mut builder, expected);
+        Fq12Target::connect(&mut builder, &pow_t, &expected_t);
+
+        let mut pw = PartialWitness::new();
+        x_t.set_witness(&mut pw, &x);
+        offset_t.set_witness(&mut pw, &offset);
+        pw.set_biguint_target(&exp_t, &exp);
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_pow_constrained() {
+        let rng = &mut rand::thread_rng();
+        let x = Fq12::rand(rng);
+        let exp: BigUint = Fr::rand(rng).into();
+        let expected = x.pow(exp.to_u64_digits());
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::empty(&mut builder);
+        let exp_bits_t = (0..exp.bits())
+            .map(|_| builder.add_virtual_bool_target_safe())
+            .collect::<Vec<_>>();
+        let pow_t = x_t.pow_constrained(&mut builder, &exp_bits_t);
+        let expected_t = Fq12Target::constant(&mut builder, expected);
+        Fq12Target::connect(&mut builder, &pow_t, &expected_t);
+
+        let mut pw = PartialWitness::new();
+        x_t.set_witness(&mut pw, &x);
+        for (i, bit_t) in exp_bits_t.iter().enumerate() {
+            pw.set_bool_target(*bit_t, exp.bit(i as u64));
+        }
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    fn check_square_and_conditional_mul(bit: bool) {
+        let rng = &mut rand::thread_rng();
+        let x = Fq12::rand(rng);
+        let base = Fq12::rand(rng);
+        let expected = if bit { x.square() * base } else { x.square() };
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::empty(&mut builder);
+        let base_t = Fq12Target::empty(&mut builder);
+        let bit_t = builder.add_virtual_bool_target_safe();
+        let fused_t = x_t.square_and_conditional_mul(&mut builder, &base_t, &bit_t);
+        let manual_t = x_t
+            .square(&mut builder)
+            .conditional_mul(&mut builder, &base_t, &bit_t);
+        Fq12Target::connect(&mut builder, &fused_t, &manual_t);
+        let expected_t = Fq12Target::constant(&mut builder, expected);
+        Fq12Target::connect(&mut builder, &fused_t, &expected_t);
+
+        let mut pw = PartialWitness::new();
+        x_t.set_witness(&mut pw, &x);
+        base_t.set_witness(&mut pw, &base);
+        pw.set_bool_target(bit_t, bit);
+        prove_and_verify(builder, pw).unwrap();
+    }
+
+    #[test]
+    fn test_square_and_conditional_mul() {
+        check_square_and_conditional_mul(false);
+        check_square_and_conditional_mul(true);
+    }
+
+    fn check_pow_windowed(x: Fq12, exp: &BigUint, cyclotomic: bool) {
+        let expected = x.pow(exp.to_u64_digits());
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::empty(&mut builder);
+        let exp_bits_t = (0..254)
+            .map(|_| builder.add_virtual_bool_target_safe())
+            .collect::<Vec<_>>();
+        let expected_t = Fq12Target::constant(&mut builder, expected);
+        // 3 does not divide 254, so the top window is padded
+        for window in [1, 3, 4] {
+            let pow_t = if cyclotomic {
+                x_t.cyclotomic_pow_windowed(&mut builder, &exp_bits_t, window)
+            } else {
+                x_t.pow_windowed(&mut builder, &exp_bits_t, window)
+            };
+            Fq12Target::connect(&mut builder, &pow_t, &expected_t);
+        }
+
+        let mut pw = PartialWitness::new();
+        x_t.set_witness(&mut pw, &x);
+        for (i, bit_t) in exp_bits_t.iter().enumerate() {
+            pw.set_bool_target(*bit_t, exp.bit(i as u64));
+        }
+        prove_and_verify(builder, pw).unwrap();
+    }
+
+    #[test]
+    fn test_pow_windowed() {
+        let rng = &mut rand::thread_rng();
+        let exp: BigUint = Fr::rand(rng).into();
+        check_pow_windowed(Fq12::rand(rng), &exp, false);
+    }
+
+    // the recoding only sees exp | 1, an even exponent goes through the final correction
+    #[test]
+    fn test_pow_windowed_even_exponents() {
+        let rng = &mut rand::thread_rng();
+        let exp: BigUint = Fr::rand(rng).into();
+        let even = (exp >> 1) << 1;
+        for exp in [even, BigUint::zero(), BigUint::from(2u32)] {
+            check_pow_windowed(Fq12::rand(rng), &exp, false);
+        }
+    }
+
+    #[test]
+    fn test_cyclotomic_pow_windowed() {
+        let rng = &mut rand::thread_rng();
+        // conj(r) / r, raised to p^2 + 1: the easy part of the final exponentiation
+        let y = random_cyclotomic(rng);
+        let x = y.frobenius_map(2) * y;
+        let exp: BigUint = Fr::rand(rng).into();
+        check_pow_windowed(x, &exp, true);
+        check_pow_windowed(x, &BigUint::zero(), true);
+    }
+
+    #[test]
+    fn test_one_and_zero() {
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let one_t = Fq12Target::one(&mut builder);
+        let zero_t = Fq12Target::zero(&mut builder);
+        let expected_one_t = Fq12Target::constant(&mut builder, Fq12::ONE);
+        let expected_zero_t = Fq12Target::constant(&mut builder, Fq12::ZERO);
+        Fq12Target::connect(&mut builder, &one_t, &expected_one_t);
+        Fq12Target::connect(&mut builder, &zero_t, &expected_zero_t);
+
+        prove_and_verify(builder, PartialWitness::new()).unwrap();
+    }
+
+    #[test]
+    fn test_from_fq() {
+        let rng = &mut rand::thread_rng();
+        let x = Fq::rand(rng);
+        let y = Fq::rand(rng);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = FqTarget::constant(&mut builder, x);
+        let y_t = FqTarget::constant(&mut builder, y);
+        let xy_t = FqTarget::constant(&mut builder, x * y);
+        let x12_t = Fq12Target::from_fq(&mut builder, &x_t);
+        let y12_t = Fq12Target::from_fq(&mut builder, &y_t);
+        let prod_t = x12_t.mul(&mut builder, &y12_t);
+        let xy12_t = Fq12Target::from_fq(&mut builder, &xy_t);
+
+        Fq12Target::connect(&mut builder, &prod_t, &xy12_t);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw);
+    }
+
+    #[test]
+    fn test_from_fq2_fq6() {
+        let rng = &mut rand::thread_rng();
+        let a = Fq2::rand(rng);
+        let b = Fq6::rand(rng);
+        let a_expected = Fq12::new(Fq6::new(a, Fq2::zero(), Fq2::zero()), Fq6::zero());
+        let b_expected = Fq12::new(b, Fq6::zero());
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let a_t = Fq2Target::constant(&mut builder, a);
+        let b_t = Fq6Target::constant(&mut builder, b);
+        let a12_t = Fq12Target::from_fq2(&mut builder, &a_t);
+        let b12_t = Fq12Target::from_fq6(&mut builder, &b_t);
+        let a_expected_t = Fq12Target::constant(&mut builder, a_expected);
+        let b_expected_t = Fq12Target::constant(&mut builder, b_expected);
+
+        Fq12Target::connect(&mut builder, &a12_t, &a_expected_t);
+        Fq12Target::connect(&mut builder, &b12_t, &b_expected_t);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw);
+    }
+
+    fn check_is_equal_up_to_conjugation(x: Fq12, y: Fq12, expected: bool) {
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::constant(&mut builder, x);
+        let y_t = Fq12Target::constant(&mut builder, y);
+        let is_equal = x_t.is_equal_up_to_conjugation(&mut builder, &y_t);
+        let expected_t = builder.constant_bool(expected);
+        builder.connect(is_equal.target, expected_t.target);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    // limbs of a with the first coefficient replaced by its unreduced value c0 + p
+    fn unreduced_limbs(a: Fq12) -> Vec<u32> {
+        let a: MyFq12 = a.into();
+        let p: BigUint = Fq::MODULUS.into();
+        a.coeffs
+            .iter()
+            .enumerate()
+            .flat_map(|(i, c)| {
+                let mut c: BigUint = (*c).into();
+                if i == 0 {
+                    c += &p;
+                }
+                let mut limbs = c.to_u32_digits();
+                limbs.resize(8, 0);
+                limbs
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_try_new_rejects_wrong_shape() {
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let coeffs = (0..11)
+            .map(|_| FqTarget::empty(&mut builder))
+            .collect::<Vec<_>>();
+        let err = Fq12Target::try_new(coeffs).unwrap_err();
+        assert_eq!(err, Fq12ShapeError::CoeffCount(11));
+        assert_eq!(err.to_string(), "Fq12Target needs 12 coefficients, got 11");
+
+        // 9 limbs, as from a nonnative config with a wider modulus
+        let limbs = (0..9)
+            .map(|_| U32Target(builder.add_virtual_target()))
+            .collect::<Vec<_>>();
+        let wide =
+            FqTarget::new(builder.biguint_to_nonnative::<Bn254Base>(&BigUintTarget { limbs }));
+        let mut coeffs = (0..12)
+            .map(|_| FqTarget::empty(&mut builder))
+            .collect::<Vec<_>>();
+        coeffs[4] = wide;
+        assert_eq!(
+            Fq12Target::try_new(coeffs).unwrap_err(),
+            Fq12ShapeError::LimbCount {
+                index: 4,
+                limbs: 9,
+                max: 8
+            }
+        );
+    }
+
+    #[test]
+    fn test_try_new_coeff_count() {
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let coeffs = (0..13)
+            .map(|_| FqTarget::empty(&mut builder))
+            .collect::<Vec<_>>();
+        let err = Fq12Target::try_new(coeffs.clone()).unwrap_err();
+        assert_eq!(err, Fq12ShapeError::CoeffCount(13));
+        assert_eq!(err.to_string(), "Fq12Target needs 12 coefficients, got 13");
+
+        let exact = Fq12Target::try_new(coeffs[..12].to_vec()).unwrap();
+        let expected = coeffs[..12]
+            .iter()
+            .flat_map(|c| c.to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(exact.to_vec(), expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "Fq12Target needs 12 coefficients, got 13")]
+    fn test_new_panics_with_description() {
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let coeffs = (0..13)
+            .map(|_| FqTarget::empty(&mut builder))
+            .collect::<Vec<_>>();
+        Fq12Target::new(coeffs);
+    }
+
+    fn check_connect_to_constant(offset: u64) {
+        let rng = &mut rand::thread_rng();
+        let x = Fq12::rand(rng);
+        let y = Fq12::rand(rng);
+        // x * y with coefficient 3 cleared, so both constant paths are used
+        let mut expected: MyFq12 = (x * y).into();
+        let cleared = expected.coeffs[3];
+        expected.coeffs[3] = Fq::zero();
+        let mut shift = MyFq12 {
+            coeffs: [Fq::zero(); 12],
+        };
+        shift.coeffs[3] = cleared;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::empty(&mut builder);
+        let y_t = Fq12Target::empty(&mut builder);
+        let shift_t = Fq12Target::constant(&mut builder, shift.into());
+        let output_t = x_t.mul(&mut builder, &y_t).sub(&mut builder, &shift_t);
+        let expected = Fq12::from(expected) + Fq12::from(offset);
+        output_t.connect_to_constant(&mut builder, &expected);
+
+        let mut pw = PartialWitness::new();
+        x_t.set_witness(&mut pw, &x);
+        y_t.set_witness(&mut pw, &y);
+        prove_and_verify(builder, pw).unwrap();
+    }
+
+    #[test]
+    fn test_connect_to_constant() {
+        check_connect_to_constant(0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_connect_to_constant_rejects_wrong_value() {
+        check_connect_to_constant(1);
+    }
+
+    fn check_assert_equal(offset: u64) {
+        let rng = &mut rand::thread_rng();
+        let x = Fq12::rand(rng);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::empty(&mut builder);
+        let square_t = x_t.square(&mut builder);
+        let expected_t = Fq12Target::constant(&mut builder, x * x + Fq12::from(offset));
+        square_t.assert_equal(&mut builder, &expected_t, "square check");
+
+        let mut pw = PartialWitness::new();
+        x_t.set_witness(&mut pw, &x);
+        prove_and_verify(builder, pw).unwrap();
+    }
+
+    #[test]
+    fn test_assert_equal() {
+        check_assert_equal(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "square check: Fq12 values differ at coefficients [0]")]
+    fn test_assert_equal_reports_label() {
+        check_assert_equal(1);
+    }
+
+    #[test]
+    fn test_mux() {
+        let rng = &mut rand::thread_rng();
+        let candidates = (0..4).map(|_| Fq12::rand(rng)).collect::<Vec<_>>();
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let candidates_t = candidates
+            .iter()
+            .map(|x| Fq12Target::constant(&mut builder, *x))
+            .collect::<Vec<_>>();
+        let mut selectors = vec![];
+        for (index, expected) in candidates.iter().enumerate() {
+            let bits_t = [
+                builder.add_virtual_bool_target_safe(),
+                builder.add_virtual_bool_target_safe(),
+            ];
+            let selected_t = Fq12Target::mux(&mut builder, &bits_t, &candidates_t);
+            let expected_t = Fq12Target::constant(&mut builder, *expected);
+            Fq12Target::connect(&mut builder, &selected_t, &expected_t);
+            selectors.push((index, bits_t));
+        }
+
+        let mut pw = PartialWitness::new();
+        for (index, bits_t) in selectors {
+            pw.set_bool_target(bits_t[0], index & 1 == 1);
+            pw.set_bool_target(bits_t[1], index & 2 == 2);
+        }
+        prove_and_verify(builder, pw).unwrap();
+    }
+
+    #[test]
+    fn test_random_access() {
+        let rng = &mut rand::thread_rng();
+        let table = (0..5).map(|_| Fq12::rand(rng)).collect::<Vec<_>>();
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let table_t = table
+            .iter()
+            .map(|x| Fq12Target::constant(&mut builder, *x))
+            .collect::<Vec<_>>();
+        let index = builder.add_virtual_target();
+        let selected_t = Fq12Target::random_access(&mut builder, index, &table_t);
+        let expected_t = Fq12Target::constant(&mut builder, table[3]);
+        Fq12Target::connect(&mut builder, &selected_t, &expected_t);
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(index, F::from_canonical_usize(3));
+        prove_and_verify(builder, pw).unwrap();
+    }
+
+    // the range check is an assert_zero, so an out-of-table index is a witness conflict
+    #[test]
+    #[should_panic]
+    fn test_random_access_rejects_padding_index() {
+        let rng = &mut rand::thread_rng();
+        let table = (0..5).map(|_| Fq12::rand(rng)).collect::<Vec<_>>();
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let table_t = table
+            .iter()
+            .map(|x| Fq12Target::constant(&mut builder, *x))
+            .collect::<Vec<_>>();
+        let index = builder.add_virtual_target();
+        Fq12Target::random_access(&mut builder, index, &table_t);
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(index, F::from_canonical_usize(6));
+        prove_and_verify(builder, pw).unwrap();
+    }
+
+    #[test]
+    fn test_is_equal_and_is_zero() {
+        let rng = &mut rand::thread_rng();
+        let a = Fq12::rand(rng);
+        let b = Fq12::rand(rng);
+        // c0 of a is below 2^254, so c0 + p still fits in 8 limbs
+        let cases = [
+            (a, unreduced_limbs(a), true),
+            (b, unreduced_limbs(a), false),
+            (Fq12::ZERO, unreduced_limbs(Fq12::ZERO), true),
+        ];
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let mut pw = PartialWitness::new();
+        for (x, limbs, expected) in cases {
+            let x_t = Fq12Target::constant(&mut builder, x);
+            let limbs_t = limbs
+                .iter()
+                .map(|&l| builder.constant(F::from_canonical_u32(l)))
+                .collect::<Vec<Target>>();
+            let y_t = Fq12Target::from_vec(&mut builder, &limbs_t);
+            let is_equal = x_t.is_equal(&mut builder, &y_t);
+            let expected_t = builder.constant_bool(expected);
+            builder.connect(is_equal.target, expected_t.target);
+        }
+        for (x, limbs, expected) in [
+            (Fq12::ZERO, unreduced_limbs(Fq12::ZERO), true),
+            (a, unreduced_limbs(a), false),
+        ] {
+            let x_t = Fq12Target::empty(&mut builder);
+            x_t.set_witness(&mut pw, &x);
+            let is_zero = x_t.is_zero(&mut builder);
+            let expected_t = builder.constant_bool(expected);
+            builder.connect(is_zero.target, expected_t.target);
+
+            let limbs_t = limbs
+                .iter()
+                .map(|&l| builder.constant(F::from_canonical_u32(l)))
+                .collect::<Vec<Target>>();
+            let y_t = Fq12Target::from_vec(&mut builder, &limbs_t);
+            let is_zero = y_t.is_zero(&mut builder);
+            let expected_t = builder.constant_bool(expected);
+            builder.connect(is_zero.target, expected_t.target);
+        }
+
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_is_equal_up_to_conjugation() {
+        let rng = &mut rand::thread_rng();
+        let x = Fq12::rand(rng);
+        let mut x_conj = x;
+        x_conj.conjugate_in_place();
+        let y = Fq12::rand(rng);
+
+        check_is_equal_up_to_conjugation(x, x, true);
+        check_is_equal_up_to_conjugation(x, x_conj, true);
+        check_is_equal_up_to_conjugation(x, y, false);
+    }
+
+    #[test]
+    fn test_frobenius_map_order_twelve() {
+        let rng = &mut rand::thread_rng();
+        let x = Fq12::rand(rng);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::constant(&mut builder, x);
+        let mut y_t = x_t.clone();
+        for _ in 0..12 {
+            y_t = y_t.frobenius_map(&mut builder, 1);
+        }
+
+        Fq12Target::connect(&mut builder, &x_t, &y_t);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw);
+    }
+
+    fn check_frobenius_map(power: usize) {
+        let rng = &mut rand::thread_rng();
+        let x = Fq12::rand(rng);
+        let expected = x.frobenius_map(power);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::constant(&mut builder, x);
+        let y_t = x_t.frobenius_map(&mut builder, power);
+        let expected_t = Fq12Target::constant(&mut builder, expected);
+
+        Fq12Target::connect(&mut builder, &y_t, &expected_t);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_frobenius_map_1() {
+        check_frobenius_map(1);
+    }
+
+    #[test]
+    fn test_frobenius_map_2() {
+        check_frobenius_map(2);
+    }
+
+    #[test]
+    fn test_frobenius_map_3() {
+        check_frobenius_map(3);
+    }
+
+    #[test]
+    fn test_frobenius_map_6() {
+        check_frobenius_map(6);
+    }
+
+    #[test]
+    fn test_frobenius_map_six_is_involution() {
+        let rng = &mut rand::thread_rng();
+        let x = Fq12::rand(rng);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::constant(&mut builder, x);
+        let y_t = x_t
+            .frobenius_map(&mut builder, 6)
+            .frobenius_map(&mut builder, 6);
+
+        Fq12Target::connect(&mut builder, &x_t, &y_t);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw);
+    }
+
+    #[test]
+    fn test_repeated_frobenius() {
+        let rng = &mut rand::thread_rng();
+        let x = Fq12::rand(rng);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::empty(&mut builder);
+        for k in 1..6 {
+            let repeated_t = x_t.repeated_frobenius(&mut builder, k);
+            let mapped_t = x_t.frobenius_map(&mut builder, k);
+            Fq12Target::connect(&mut builder, &repeated_t, &mapped_t);
+            let expected_t = Fq12Target::constant(&mut builder, x.frobenius_map(k));
+            Fq12Target::connect(&mut builder, &repeated_t, &expected_t);
+        }
+        let wrapped_t = x_t.repeated_frobenius(&mut builder, 13);
+        let once_t = x_t.frobenius_map(&mut builder, 1);
+        Fq12Target::connect(&mut builder, &wrapped_t, &once_t);
+
+        let mut pw = PartialWitness::new();
+        x_t.set_witness(&mut pw, &x);
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_assert_eq_product() {
+        let rng = &mut rand::thread_rng();
+        let factors = (0..3).map(|_| Fq12::rand(rng)).collect::<Vec<_>>();
+        let expected = factors.iter().product::<Fq12>();
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let factors_t = factors
+            .iter()
+            .map(|x| Fq12Target::constant(&mut builder, *x))
+            .collect::<Vec<_>>();
+        let expected_t = Fq12Target::constant(&mut builder, expected);
+
+        Fq12Target::assert_eq_product(&mut builder, &factors_t, &expected_t);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw);
     }
 
-    fn deserialize(
-        src: &mut Buffer,
-        common_data: &CommonCircuitData<F, D>,
-    ) -> plonky2::util::serialization::IoResult<Self>
-    where
-        Self: Sized,
-    {
-        let x = Fq12Target::deserialize(src, common_data)?;
-        let offset = Fq12Target::deserialize(src, common_data)?;
-        let output = Fq12Target::deserialize(src, common_data)?;
-        let exp_val = src.read_target()?;
+    #[test]
+    fn test_cyclotomic_inverse() {
+        let rng = &mut rand::thread_rng();
+        let x = random_cyclotomic(rng);
 
-        Ok(Self {
-            x,
-            offset,
-            output,
-            exp_val,
-        })
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::empty(&mut builder);
+        let inv_t = x_t.inv(&mut builder);
+        let cyclotomic_inv_t = x_t.cyclotomic_inverse(&mut builder);
+        Fq12Target::connect(&mut builder, &inv_t, &cyclotomic_inv_t);
+
+        let mut pw = PartialWitness::new();
+        x_t.set_witness(&mut pw, &x);
+        prove_and_verify(builder, pw).unwrap();
     }
-}
 
-impl<F: RichField + Extendable<D>, const D: usize> Fq12Target<F, D> {
-    pub fn to_vec(&self) -> Vec<Target> {
-        self.coeffs.iter().flat_map(|c| c.to_vec()).collect()
+    #[test]
+    fn test_assert_conjugate_is_inverse() {
+        let rng = &mut rand::thread_rng();
+        let x = random_cyclotomic(rng);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::constant(&mut builder, x);
+        x_t.assert_conjugate_is_inverse(&mut builder);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw);
     }
 
-    pub fn from_vec(builder: &mut CircuitBuilder<F, D>, input: &[Target]) -> Self {
-        let num_limbs = 8;
-        assert_eq!(input.len(), 12 * num_limbs);
-        let coeffs = input
-            .iter()
-            .cloned()
-            .chunks(num_limbs)
-            .into_iter()
-            .map(|chunk| FqTarget::from_vec(builder, &chunk.collect_vec()))
-            .collect_vec();
-        Fq12Target {
-            coeffs: coeffs.try_into().unwrap(),
+    #[test]
+    #[should_panic]
+    fn test_assert_conjugate_is_inverse_fails_for_general_element() {
+        let rng = &mut rand::thread_rng();
+        let x = Fq12::rand(rng);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::constant(&mut builder, x);
+        x_t.assert_conjugate_is_inverse(&mut builder);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw);
+    }
+
+    #[test]
+    fn test_mul_constant() {
+        let rng = &mut rand::thread_rng();
+        let a = Fq12::rand(rng);
+        let dense = Fq12::rand(rng);
+        let sparse = Fq12::new(
+            Fq6::new(Fq2::rand(rng), Fq2::zero(), Fq2::zero()),
+            Fq6::new(Fq2::rand(rng), Fq2::ONE, Fq2::zero()),
+        );
+        // Fq-level zeros, ones and minus ones inside the Fq2 slots
+        let special = Fq12::new(
+            Fq6::new(Fq2::new(Fq::zero(), Fq::rand(rng)), -Fq2::ONE, Fq2::zero()),
+            Fq6::new(Fq2::new(Fq::ONE, -Fq::ONE), Fq2::new(Fq::zero(), Fq::ONE), Fq2::ONE),
+        );
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let a_t = Fq12Target::constant(&mut builder, a);
+        for c in [dense, sparse, special] {
+            let c_t = Fq12Target::constant(&mut builder, c);
+            let expected_t = a_t.mul(&mut builder, &c_t);
+            let output_t = a_t.mul_constant(&mut builder, c);
+            Fq12Target::connect(&mut builder, &output_t, &expected_t);
         }
+
+        let pw = PartialWitness::new();
+        prove_and_verify(builder, pw).unwrap();
     }
 
-    pub fn set_witness<W: WitnessWrite<F>>(&self, pw: &mut W, value: &Fq12) {
-        let my_value: MyFq12 = value.clone().into();
-        self.coeffs
-            .iter()
-            .cloned()
-            .zip(my_value.coeffs)
-            .map(|(c_t, c)| c_t.set_witness(pw, &c))
-            .for_each(drop);
+    #[test]
+    fn test_mul_constant_gate_reduction() {
+        let rng = &mut rand::thread_rng();
+        let sparse = Fq12::new(
+            Fq6::new(Fq2::rand(rng), Fq2::zero(), Fq2::zero()),
+            Fq6::new(Fq2::rand(rng), Fq2::ONE, Fq2::zero()),
+        );
+
+        let num_gates = |use_constant: bool| {
+            let config = CircuitConfig::standard_ecc_config();
+            let mut builder = CircuitBuilder::<F, D>::new(config);
+            let a_t = Fq12Target::empty(&mut builder);
+            let before = builder.num_gates();
+            if use_constant {
+                a_t.mul_constant(&mut builder, sparse);
+            } else {
+                let c_t = Fq12Target::constant(&mut builder, sparse);
+                a_t.mul(&mut builder, &c_t);
+            }
+            builder.num_gates() - before
+        };
+        let mul_gates = num_gates(false);
+        let mul_constant_gates = num_gates(true);
+        println!("mul: {mul_gates} gates, mul_constant: {mul_constant_gates} gates");
+        assert!(mul_constant_gates < mul_gates);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use ark_bn254::{Fq, Fq12};
-    use ark_ff::Field;
-    use ark_std::UniformRand;
-    use num_bigint::BigUint;
-    use plonky2::{
-        field::{goldilocks_field::GoldilocksField, types::Field as Plonky2Field},
-        iop::{target::Target, witness::PartialWitness},
-        plonk::{
-            circuit_builder::CircuitBuilder, circuit_data::CircuitConfig,
-            config::PoseidonGoldilocksConfig,
-        },
-    };
-    use rand::Rng;
+    #[test]
+    fn test_mul_by_034() {
+        let rng = &mut rand::thread_rng();
+        let a = Fq12::rand(rng);
+        let c0 = Fq2::rand(rng);
+        let c3 = Fq2::rand(rng);
+        let c4 = Fq2::rand(rng);
+        let mut expected = a;
+        expected.mul_by_034(&c0, &c3, &c4);
 
-    use super::{from_biguint_to_fq, Fq12Target};
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let a_t = Fq12Target::empty(&mut builder);
+        let c0_t = Fq2Target::empty(&mut builder);
+        let c3_t = Fq2Target::empty(&mut builder);
+        let c4_t = Fq2Target::empty(&mut builder);
+        let output_t = a_t.mul_by_034(&mut builder, &c0_t, &c3_t, &c4_t);
+        let zero = Fq2Target::constant(&mut builder, Fq2::zero());
+        let dense_t = Fq12Target::from_fq2_coeffs([
+            c0_t.clone(),
+            zero.clone(),
+            zero.clone(),
+            c3_t.clone(),
+            c4_t.clone(),
+            zero,
+        ]);
+        let dense_output_t = a_t.mul(&mut builder, &dense_t);
+        Fq12Target::connect(&mut builder, &output_t, &dense_output_t);
+        let expected_t = Fq12Target::constant(&mut builder, expected);
+        Fq12Target::connect(&mut builder, &output_t, &expected_t);
 
-    type F = GoldilocksField;
-    type C = PoseidonGoldilocksConfig;
-    const D: usize = 2;
+        let mut pw = PartialWitness::new();
+        a_t.set_witness(&mut pw, &a);
+        c0_t.set_witness(&mut pw, &c0);
+        c3_t.set_witness(&mut pw, &c3);
+        c4_t.set_witness(&mut pw, &c4);
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
 
     #[test]
-    fn test_from_to_vec() {
+    fn test_num_public_inputs() {
         let rng = &mut rand::thread_rng();
         let a = Fq12::rand(rng);
+
         let config = CircuitConfig::standard_ecc_config();
         let mut builder = CircuitBuilder::<F, D>::new(config);
         let a_t = Fq12Target::constant(&mut builder, a);
+        let before = builder.num_public_inputs();
+        a_t.register_public_inputs(&mut builder);
+        assert_eq!(
+            builder.num_public_inputs() - before,
+            Fq12Target::<F, D>::NUM_PUBLIC_INPUTS
+        );
 
-        let a_vec = a_t.to_vec();
-        let restored_a_t = Fq12Target::from_vec(&mut builder, &a_vec);
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        assert_eq!(
+            proof.public_inputs.len(),
+            Fq12Target::<F, D>::NUM_PUBLIC_INPUTS
+        );
+    }
 
-        Fq12Target::connect(&mut builder, &a_t, &restored_a_t);
+    #[test]
+    fn test_connect_to_public_inputs() {
+        let rng = &mut rand::thread_rng();
+        let a = Fq12::rand(rng);
+        let b = Fq12::rand(rng);
+
+        // inner circuit: exposes a * b for witnesses a, b
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+        let a_t = Fq12Target::empty(&mut builder);
+        let b_t = Fq12Target::empty(&mut builder);
+        let c_t = a_t.mul(&mut builder, &b_t);
+        c_t.register_public_inputs(&mut builder);
+        let mut pw = PartialWitness::new();
+        a_t.set_witness(&mut pw, &a);
+        b_t.set_witness(&mut pw, &b);
+        let inner_data = builder.build::<C>();
+        let inner_proof = inner_data.prove(pw).unwrap();
+
+        // outer circuit: verifies the inner proof and connects its output to a * b
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let proof_t = builder.add_virtual_proof_with_pis(&inner_data.common);
+        let vd_t = builder.constant_verifier_data(&inner_data.verifier_only);
+        builder.verify_proof::<C>(&proof_t, &vd_t, &inner_data.common);
+        let a_t = Fq12Target::constant(&mut builder, a);
+        let b_t = Fq12Target::constant(&mut builder, b);
+        let c_t = a_t.mul(&mut builder, &b_t);
+        c_t.connect_to_public_inputs(&mut builder, &proof_t.public_inputs);
+        let mut pw = PartialWitness::new();
+        pw.set_proof_with_pis_target(&proof_t, &inner_proof);
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        data.verify(proof).unwrap();
+    }
+
+    #[test]
+    fn test_register_as_public_inputs() {
+        let rng = &mut rand::thread_rng();
+        let a = Fq12::rand(rng);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let a_t = Fq12Target::constant(&mut builder, a);
+        let targets = a_t.register_as_public_inputs(&mut builder);
+        assert_eq!(targets.len(), Fq12Target::<F, D>::NUM_PUBLIC_INPUTS);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        let restored = Fq12Target::<F, D>::value_from_public_inputs(
+            &proof.public_inputs[..Fq12Target::<F, D>::NUM_PUBLIC_INPUTS],
+        );
+        assert_eq!(restored, a);
+        data.verify(proof).unwrap();
+    }
+
+    // x^((p^12 - 1) / 12) has order dividing 12
+    fn twelfth_root_of_unity<R: Rng>(rng: &mut R) -> Fq12 {
+        let p: BigUint = Fq::MODULUS.into();
+        let exp = (p.pow(12) - 1u32) / 12u32;
+        Fq12::rand(rng).pow(exp.to_u64_digits())
+    }
+
+    #[test]
+    fn test_assert_pow_is_one() {
+        let rng = &mut rand::thread_rng();
+        let x = twelfth_root_of_unity(rng);
+        assert_eq!(x.pow([12u64]), Fq12::ONE);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::constant(&mut builder, x);
+        x_t.assert_pow_is_one(&mut builder, &BigUint::from(12u32));
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_assert_pow_is_one_fails_for_non_root() {
+        let rng = &mut rand::thread_rng();
+        let x = Fq12::rand(rng);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::constant(&mut builder, x);
+        x_t.assert_pow_is_one(&mut builder, &BigUint::from(12u32));
 
         let pw = PartialWitness::new();
         let data = builder.build::<C>();
@@ -481,75 +2795,266 @@ mod tests {
     }
 
     #[test]
-    fn test_from_biguint_to_fq() {
+    fn test_as_fq2_coeffs() {
         let rng = &mut rand::thread_rng();
-        let x = Fq::rand(rng);
-        let x_biguint: BigUint = x.into();
-        let converted_x = from_biguint_to_fq(x_biguint);
-        assert_eq!(x, converted_x);
+        let a = Fq12::rand(rng);
+        let expected = [a.c0.c0, a.c0.c1, a.c0.c2, a.c1.c0, a.c1.c1, a.c1.c2];
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let a_t = Fq12Target::constant(&mut builder, a);
+        let coeffs_t = a_t.as_fq2_coeffs();
+        for (c_t, c) in coeffs_t.iter().zip(expected) {
+            let expected_t = Fq2Target::constant(&mut builder, c);
+            Fq2Target::connect(&mut builder, c_t, &expected_t);
+        }
+        // each Fq2 pairs flat coefficients k and k + 6, with k the power of w
+        let flat_t = a_t.coeffs();
+        for (i, k) in [0, 2, 4, 1, 3, 5].into_iter().enumerate() {
+            FqTarget::connect(&mut builder, &coeffs_t[i].coeffs[0], &flat_t[k]);
+            FqTarget::connect(&mut builder, &coeffs_t[i].coeffs[1], &flat_t[k + 6]);
+        }
+        let restored_t = Fq12Target::from_fq2_coeffs(coeffs_t);
+        Fq12Target::connect(&mut builder, &restored_t, &a_t);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
     }
 
     #[test]
-    fn test_fq12_mul_circuit() {
+    fn test_flat_layout_roundtrip() {
+        let rng = &mut rand::thread_rng();
+        let a = Fq12::rand(rng);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let a_t = Fq12Target::empty(&mut builder);
+        // the flat views are a reordering of the tower's own targets
+        let restored_t = Fq12Target::from_coeffs(a_t.coeffs());
+        assert_eq!(restored_t.to_vec(), a_t.to_vec());
+        let restored_t = Fq12Target::new(a_t.coeffs().to_vec());
+        assert_eq!(restored_t.to_vec(), a_t.to_vec());
+        let from_vec_t = Fq12Target::from_vec(&mut builder, &a_t.to_vec());
+        Fq12Target::connect(&mut builder, &from_vec_t, &a_t);
+
+        // set in the flat MyFq12 layout, read back in the tower
+        let c0_t = Fq6Target::constant(&mut builder, a.c0);
+        let c1_t = Fq6Target::constant(&mut builder, a.c1);
+        Fq6Target::connect(&mut builder, &a_t.c0, &c0_t);
+        Fq6Target::connect(&mut builder, &a_t.c1, &c1_t);
+        a_t.connect_to_constant(&mut builder, &a);
+
+        let mut pw = PartialWitness::new();
+        a_t.set_witness(&mut pw, &a);
+        prove_and_verify(builder, pw).unwrap();
+    }
+
+    #[test]
+    fn test_fq6_pair_roundtrip() {
         let rng = &mut rand::thread_rng();
         let a = Fq12::rand(rng);
         let b = Fq12::rand(rng);
-        let c_expected = a * b;
 
         let config = CircuitConfig::standard_ecc_config();
         let mut builder = CircuitBuilder::<F, D>::new(config);
-        let a_t = Fq12Target::constant(&mut builder, a);
+        let a_t = Fq12Target::empty(&mut builder);
+        let (c0_t, c1_t) = a_t.as_fq6_pair();
+        let c0_expected_t = Fq6Target::constant(&mut builder, a.c0);
+        let c1_expected_t = Fq6Target::constant(&mut builder, a.c1);
+        Fq6Target::connect(&mut builder, &c0_t, &c0_expected_t);
+        Fq6Target::connect(&mut builder, &c1_t, &c1_expected_t);
+        let restored_t = Fq12Target::from_fq6_pair(c0_t, c1_t);
+        Fq12Target::connect(&mut builder, &restored_t, &a_t);
+
+        // Fq12 arithmetic on the pair: (a0 + a1 w) + b = (a0 + b0) + (a1 + b1) w
         let b_t = Fq12Target::constant(&mut builder, b);
-        let c_t = a_t.mul(&mut builder, &b_t);
+        let (a0_t, a1_t) = a_t.as_fq6_pair();
+        let (b0_t, b1_t) = b_t.as_fq6_pair();
+        let s0_t = a0_t.add(&mut builder, &b0_t);
+        let s1_t = a1_t.add(&mut builder, &b1_t);
+        let sum_t = Fq12Target::from_fq6_pair(s0_t, s1_t);
+        let sum_expected_t = Fq12Target::constant(&mut builder, a + b);
+        Fq12Target::connect(&mut builder, &sum_t, &sum_expected_t);
+
+        let mut pw = PartialWitness::new();
+        a_t.set_witness(&mut pw, &a);
+        prove_and_verify(builder, pw).unwrap();
+    }
+
+    #[test]
+    fn test_set_witness_my() {
+        let rng = &mut rand::thread_rng();
+        let a = Fq12::rand(rng);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let a_t = Fq12Target::empty(&mut builder);
+        let square_t = a_t.square(&mut builder);
+        square_t.register_public_inputs(&mut builder);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        a_t.set_witness(&mut pw, &a);
+        let proof = data.prove(pw).unwrap();
+        let mut pw = PartialWitness::new();
+        a_t.set_witness_my(&mut pw, &MyFq12::from(a));
+        let proof_my = data.prove(pw).unwrap();
+        // whole proofs may differ, the FRI proof of work nonce is searched in parallel
+        assert_eq!(proof.public_inputs, proof_my.public_inputs);
+        data.verify(proof_my).unwrap();
+    }
+
+    #[test]
+    fn test_set_witness_biguints() {
+        let rng = &mut rand::thread_rng();
+        let a = Fq12::rand(rng);
+        let values = MyFq12::from(a).coeffs.map(BigUint::from);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let a_t = Fq12Target::empty(&mut builder);
+        let expected_t = Fq12Target::constant(&mut builder, a);
+        Fq12Target::connect(&mut builder, &a_t, &expected_t);
+
+        let mut pw = PartialWitness::new();
+        a_t.set_witness_biguints(&mut pw, &values);
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "not reduced mod p")]
+    fn test_set_witness_biguints_rejects_out_of_range() {
+        let mut values: [BigUint; 12] = Default::default();
+        values[3] = Fq::MODULUS.into();
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let a_t = Fq12Target::empty(&mut builder);
+        let mut pw = PartialWitness::<F>::new();
+        a_t.set_witness_biguints(&mut pw, &values);
+    }
+
+    #[test]
+    fn test_fq12_square_circuit() {
+        let rng = &mut rand::thread_rng();
+        let a = Fq12::rand(rng);
+        let c_expected = a.square();
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let a_t = Fq12Target::constant(&mut builder, a);
+        let c_t = a_t.square(&mut builder);
+        let c_mul_t = a_t.mul(&mut builder, &a_t);
         let c_expected_t = Fq12Target::constant(&mut builder, c_expected);
 
         Fq12Target::connect(&mut builder, &c_expected_t, &c_t);
+        Fq12Target::connect(&mut builder, &c_mul_t, &c_t);
 
         let pw = PartialWitness::new();
         let data = builder.build::<C>();
-        let _proof = data.prove(pw);
+        let _proof = data.prove(pw).unwrap();
     }
 
     #[test]
-    fn test_fq12_inv_circuit() {
+    fn test_cyclotomic_square() {
         let rng = &mut rand::thread_rng();
-        let x: Fq12 = Fq12::rand(rng);
-        let inv_x_expected = x.inverse().unwrap();
+        let x = Fq12::rand(rng);
 
         let config = CircuitConfig::standard_ecc_config();
         let mut builder = CircuitBuilder::<F, D>::new(config);
         let x_t = Fq12Target::constant(&mut builder, x);
-        let inv_x_t = x_t.inv(&mut builder);
-        let inv_x_expected_t = Fq12Target::constant(&mut builder, inv_x_expected);
+        let x_conj_t = x_t.conjugate(&mut builder);
+        let a_t = x_conj_t.div(&mut builder, &x_t);
+        let c_t = a_t.cyclotomic_square(&mut builder);
+        let c_mul_t = a_t.mul(&mut builder, &a_t);
 
-        Fq12Target::connect(&mut builder, &inv_x_t, &inv_x_expected_t);
+        Fq12Target::connect(&mut builder, &c_mul_t, &c_t);
 
         let pw = PartialWitness::new();
         let data = builder.build::<C>();
-        dbg!(data.common.degree_bits());
-        let _proof = data.prove(pw);
+        let _proof = data.prove(pw).unwrap();
     }
 
     #[test]
-    fn test_fq12_pow_circuit() {
+    fn test_cyclotomic_square_gate_reduction() {
+        let num_gates = |use_cyclotomic: bool| {
+            let config = CircuitConfig::standard_ecc_config();
+            let mut builder = CircuitBuilder::<F, D>::new(config);
+            let a_t = Fq12Target::empty(&mut builder);
+            let before = builder.num_gates();
+            if use_cyclotomic {
+                a_t.cyclotomic_square(&mut builder);
+            } else {
+                a_t.square(&mut builder);
+            }
+            builder.num_gates() - before
+        };
+        let square_gates = num_gates(false);
+        let cyclotomic_gates = num_gates(true);
+        assert!(cyclotomic_gates < square_gates);
+    }
+
+    #[test]
+    fn test_fq12_square_gate_reduction() {
+        let num_gates = |use_square: bool| {
+            let config = CircuitConfig::standard_ecc_config();
+            let mut builder = CircuitBuilder::<F, D>::new(config);
+            let a_t = Fq12Target::empty(&mut builder);
+            let before = builder.num_gates();
+            if use_square {
+                a_t.square(&mut builder);
+            } else {
+                a_t.mul(&mut builder, &a_t);
+            }
+            builder.num_gates() - before
+        };
+        let mul_gates = num_gates(false);
+        let square_gates = num_gates(true);
+        assert!(square_gates < mul_gates);
+    }
+
+    #[test]
+    fn test_mul_karatsuba() {
         let rng = &mut rand::thread_rng();
-        let x: Fq12 = Fq12::rand(rng);
-        let exp: u64 = rng.gen();
-        let pow_expected = x.pow([exp]);
+        let a = Fq12::rand(rng);
+        let b = Fq12::rand(rng);
 
         let config = CircuitConfig::standard_ecc_config();
         let mut builder = CircuitBuilder::<F, D>::new(config);
-        let x_t = Fq12Target::constant(&mut builder, x);
-        let offset = Fq12Target::constant(&mut builder, Fq12::ONE);
-        let exp_val = builder.constant(F::from_canonical_u64(exp));
-        let pow_x_t = x_t.pow(&mut builder, &offset, exp_val);
-        let pow_x_expected_t = Fq12Target::constant(&mut builder, pow_expected);
+        let a_t = Fq12Target::empty(&mut builder);
+        let b_t = Fq12Target::empty(&mut builder);
+        let flat_t = a_t.mul(&mut builder, &b_t);
+        let karatsuba_t = a_t.mul_karatsuba(&mut builder, &b_t);
+        Fq12Target::connect(&mut builder, &flat_t, &karatsuba_t);
+        let expected_t = Fq12Target::constant(&mut builder, a * b);
+        Fq12Target::connect(&mut builder, &karatsuba_t, &expected_t);
 
-        Fq12Target::connect(&mut builder, &pow_x_t, &pow_x_expected_t);
+        let mut pw = PartialWitness::new();
+        a_t.set_witness(&mut pw, &a);
+        b_t.set_witness(&mut pw, &b);
+        prove_and_verify(builder, pw).unwrap();
+    }
 
-        let pw = PartialWitness::new();
-        let data = builder.build::<C>();
-        dbg!(data.common.degree_bits());
-        let _proof = data.prove(pw);
+    #[test]
+    fn test_mul_karatsuba_gate_reduction() {
+        let num_gates = |use_karatsuba: bool| {
+            let config = CircuitConfig::standard_ecc_config();
+            let mut builder = CircuitBuilder::<F, D>::new(config);
+            let a_t = Fq12Target::empty(&mut builder);
+            let b_t = Fq12Target::empty(&mut builder);
+            let before = builder.num_gates();
+            if use_karatsuba {
+                a_t.mul_karatsuba(&mut builder, &b_t);
+            } else {
+                a_t.mul(&mut builder, &b_t);
+            }
+            builder.num_gates() - before
+        };
+        let mul_gates = num_gates(false);
+        let karatsuba_gates = num_gates(true);
+        println!("mul: {mul_gates} gates, mul_karatsuba: {karatsuba_gates} gates");
+        assert!(karatsuba_gates < mul_gates);
     }
 }
@@ -23,6 +23,9 @@ use crate::fields::{fq_target::FqTarget, native::from_biguint_to_fq};
 
 use super::native::sgn0_fq2;
 
+// xi = XI_0 + u is the nonresidue the tower is built on: v^3 = xi in Fq6, w^6 = xi in Fq12
+const XI_0: usize = 9;
+
 #[derive(Clone, Debug, Default)]
 pub struct Fq2Target<F: RichField + Extendable<D>, const D: usize> {
     pub coeffs: [FqTarget<F, D>; 2],
@@ -75,9 +78,23 @@ impl<F: RichField + Extendable<D>, const D: usize> Fq2Target<F, D> {
         BoolTarget::new_unsafe(is_equal)
     }
 
+    // zero mod p: both components are reduced first, so a component represented
+    // as p counts as zero too
     pub fn is_zero(&self, builder: &mut CircuitBuilder<F, D>) -> BoolTarget {
-        let zero = Self::constant(builder, Fq2::ZERO);
-        self.is_equal(builder, &zero)
+        let c0_zero = self.coeffs[0].reduce(builder).is_zero(builder);
+        let c1_zero = self.coeffs[1].reduce(builder).is_zero(builder);
+        builder.and(c0_zero, c1_zero)
+    }
+
+    pub fn assert_zero(&self, builder: &mut CircuitBuilder<F, D>) {
+        let is_zero = self.is_zero(builder);
+        builder.assert_one(is_zero.target);
+    }
+
+    // e.g. the x difference in G2 addition, which is zero exactly when P = Q or P = -Q
+    pub fn assert_nonzero(&self, builder: &mut CircuitBuilder<F, D>) {
+        let is_zero = self.is_zero(builder);
+        builder.assert_zero(is_zero.target);
     }
 
     pub fn constant(builder: &mut CircuitBuilder<F, D>, c: Fq2) -> Self {
@@ -137,8 +154,8 @@ impl<F: RichField + Extendable<D>, const D: usize> Fq2Target<F, D> {
     }
 
     pub fn mul_scalar_const(&self, builder: &mut CircuitBuilder<F, D>, c: &Fq) -> Self {
-        let c = FqTarget::constant(builder, c.clone());
-        self.mul_scalar(builder, &c)
+        let coeffs = self.coeffs.each_ref().map(|x| x.mul_const(builder, c));
+        Fq2Target { coeffs }
     }
 
     pub fn mul(&self, builder: &mut CircuitBuilder<F, D>, rhs: &Self) -> Self {
@@ -161,6 +178,51 @@ impl<F: RichField + Extendable<D>, const D: usize> Fq2Target<F, D> {
         Fq2Target { coeffs: [c0, c1] }
     }
 
+    // same product as mul with 3 nonnative muls instead of 4:
+    // a1 b0 + a0 b1 = (a0 + a1)(b0 + b1) - a0 b0 - a1 b1
+    pub fn mul_karatsuba(&self, builder: &mut CircuitBuilder<F, D>, rhs: &Self) -> Self {
+        let [a0, a1] = &self.coeffs;
+        let [b0, b1] = &rhs.coeffs;
+        let a0_b0 = a0.mul(builder, b0);
+        let a1_b1 = a1.mul(builder, b1);
+        let c0 = a0_b0.sub(builder, &a1_b1);
+
+        let a0_plus_a1 = a0.add(builder, a1);
+        let b0_plus_b1 = b0.add(builder, b1);
+        let cross = a0_plus_a1.mul(builder, &b0_plus_b1);
+        let c1 = cross.sub(builder, &a0_b0);
+        let c1 = c1.sub(builder, &a1_b1);
+
+        Fq2Target { coeffs: [c0, c1] }
+    }
+
+    pub fn mul_const(&self, builder: &mut CircuitBuilder<F, D>, c: &Fq2) -> Self {
+        if c.c1.is_zero() {
+            return self.mul_scalar_const(builder, &c.c0);
+        }
+        if c.c0.is_zero() {
+            // (a0 + a1 u) * c1 u = -a1 c1 + a0 c1 u
+            let a0_c1 = self.coeffs[0].mul_const(builder, &c.c1);
+            let a1_c1 = self.coeffs[1].mul_const(builder, &c.c1);
+            let out0 = a1_c1.neg(builder);
+            return Fq2Target {
+                coeffs: [out0, a0_c1],
+            };
+        }
+        let a0 = self.coeffs[0].clone();
+        let a1 = self.coeffs[1].clone();
+        // (a0 + a1 u) * (c0 + c1 u) = (a0 c0 - a1 c1) + (a0 c1 + a1 c0) u
+        let a0_c0 = a0.mul_const(builder, &c.c0);
+        let a1_c1 = a1.mul_const(builder, &c.c1);
+        let out0 = a0_c0.sub(builder, &a1_c1);
+        let a0_c1 = a0.mul_const(builder, &c.c1);
+        let a1_c0 = a1.mul_const(builder, &c.c0);
+        let out1 = a0_c1.add(builder, &a1_c0);
+        Fq2Target {
+            coeffs: [out0, out1],
+        }
+    }
+
     pub fn mul_w6<const XI_0: usize>(&self, builder: &mut CircuitBuilder<F, D>) -> Self {
         let a0 = self.coeffs[0].clone();
         let a1 = self.coeffs[1].clone();
@@ -174,6 +236,17 @@ impl<F: RichField + Extendable<D>, const D: usize> Fq2Target<F, D> {
         }
     }
 
+    // multiplication by the tower nonresidue xi = 9 + u
+    pub fn mul_by_nonresidue(&self, builder: &mut CircuitBuilder<F, D>) -> Self {
+        self.mul_w6::<XI_0>(builder)
+    }
+
+    // multiplication by xi^-1, a constant Fq2 mul
+    pub fn mul_by_nonresidue_inv(&self, builder: &mut CircuitBuilder<F, D>) -> Self {
+        let xi = Fq2::new(Fq::from(XI_0 as u64), Fq::ONE);
+        self.mul_const(builder, &xi.inverse().unwrap())
+    }
+
     // this method fails if self is zero
     pub fn inv(&self, builder: &mut CircuitBuilder<F, D>) -> Self {
         let inv = Self::empty(builder);
@@ -438,8 +511,12 @@ impl<F: RichField + Extendable<D>, const D: usize> Fq2Target<F, D> {
     }
 
     pub fn from_vec(builder: &mut CircuitBuilder<F, D>, input: &[Target]) -> Self {
-        let num_limbs = 8;
-        assert_eq!(input.len(), 2 * num_limbs);
+        let num_limbs = FqTarget::<F, D>::NUM_LIMBS;
+        assert_eq!(
+            input.len(),
+            2 * num_limbs,
+            "Fq2Target::from_vec expects 2 coefficients of {num_limbs} limbs"
+        );
         let coeffs = input
             .iter()
             .cloned()
@@ -465,8 +542,8 @@ impl<F: RichField + Extendable<D>, const D: usize> Fq2Target<F, D> {
 
 #[cfg(test)]
 mod tests {
-    use ark_bn254::{Fq, Fq2};
-    use ark_ff::Field;
+    use ark_bn254::{Fq, Fq2, Fq6Config};
+    use ark_ff::{Field, Fp6Config};
     use ark_std::UniformRand;
     use num_traits::{One, Zero};
     use plonky2::{
@@ -479,7 +556,7 @@ mod tests {
     };
     use rand::Rng;
 
-    use crate::fields::native::sgn0_fq2;
+    use crate::{fields::native::sgn0_fq2, utils::prove_and_verify};
 
     use super::Fq2Target;
 
@@ -506,6 +583,30 @@ mod tests {
         let _proof = data.prove(pw);
     }
 
+    #[test]
+    fn test_mul_by_nonresidue() {
+        let rng = &mut rand::thread_rng();
+        let x: Fq2 = Fq2::rand(rng);
+        let x_xi = x * Fq6Config::NONRESIDUE;
+        let x_xi_inv = x / Fq6Config::NONRESIDUE;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq2Target::empty(&mut builder);
+        let x_xi_t = x_t.mul_by_nonresidue(&mut builder);
+        let x_xi_inv_t = x_t.mul_by_nonresidue_inv(&mut builder);
+        let x_xi_expected = Fq2Target::constant(&mut builder, x_xi);
+        let x_xi_inv_expected = Fq2Target::constant(&mut builder, x_xi_inv);
+        Fq2Target::connect(&mut builder, &x_xi_t, &x_xi_expected);
+        Fq2Target::connect(&mut builder, &x_xi_inv_t, &x_xi_inv_expected);
+        let roundtrip_t = x_xi_t.mul_by_nonresidue_inv(&mut builder);
+        Fq2Target::connect(&mut builder, &roundtrip_t, &x_t);
+
+        let mut pw = PartialWitness::new();
+        x_t.set_witness(&mut pw, &x);
+        prove_and_verify(builder, pw).unwrap();
+    }
+
     // todo! fails when true, true, true
 
     #[test]
@@ -548,6 +649,42 @@ mod tests {
         let _proof = data.prove(pw);
     }
 
+    fn check_assert_zero_nonzero(x: Fq2, assert_zero: bool) {
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq2Target::empty(&mut builder);
+        if assert_zero {
+            x_t.assert_zero(&mut builder);
+        } else {
+            x_t.assert_nonzero(&mut builder);
+        }
+
+        let mut pw = PartialWitness::new();
+        x_t.set_witness(&mut pw, &x);
+        prove_and_verify(builder, pw).unwrap();
+    }
+
+    #[test]
+    fn test_assert_zero_and_nonzero() {
+        let rng = &mut rand::thread_rng();
+        check_assert_zero_nonzero(Fq2::zero(), true);
+        check_assert_zero_nonzero(Fq2::rand(rng), false);
+        // only one component zero is still nonzero
+        check_assert_zero_nonzero(Fq2::new(Fq::zero(), Fq::rand(rng)), false);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_assert_nonzero_fails_on_zero() {
+        check_assert_zero_nonzero(Fq2::zero(), false);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_assert_zero_fails_on_nonzero() {
+        check_assert_zero_nonzero(Fq2::rand(&mut rand::thread_rng()), true);
+    }
+
     #[test]
     fn test_inv0_input_nonzero_success() {
         let rng = &mut rand::thread_rng();
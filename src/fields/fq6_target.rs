@@ -0,0 +1,394 @@
+use ark_bn254::{Fq2, Fq6};
+use itertools::Itertools;
+use plonky2::{
+    field::extension::Extendable,
+    hash::hash_types::RichField,
+    iop::{
+        target::{BoolTarget, Target},
+        witness::WitnessWrite,
+    },
+    plonk::circuit_builder::CircuitBuilder,
+};
+
+use crate::fields::{fq2_target::Fq2Target, fq_target::FqTarget};
+
+// Fq6 = Fq2[v] / (v^3 - (9 + u)), stored as c0 + c1 v + c2 v^2
+#[derive(Clone, Debug, Default)]
+pub struct Fq6Target<F: RichField + Extendable<D>, const D: usize> {
+    pub coeffs: [Fq2Target<F, D>; 3],
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Fq6Target<F, D> {
+    pub fn empty(builder: &mut CircuitBuilder<F, D>) -> Self {
+        let coeffs = [(); 3]
+            .iter()
+            .map(|_| Fq2Target::empty(builder))
+            .collect_vec()
+            .try_into()
+            .unwrap();
+        Fq6Target { coeffs }
+    }
+
+    pub fn new(coeffs: Vec<Fq2Target<F, D>>) -> Self {
+        Fq6Target {
+            coeffs: coeffs.try_into().unwrap(),
+        }
+    }
+
+    pub fn connect(builder: &mut CircuitBuilder<F, D>, lhs: &Self, rhs: &Self) {
+        for i in 0..3 {
+            Fq2Target::connect(builder, &lhs.coeffs[i], &rhs.coeffs[i]);
+        }
+    }
+
+    pub fn select(
+        builder: &mut CircuitBuilder<F, D>,
+        lhs: &Self,
+        rhs: &Self,
+        flag: &BoolTarget,
+    ) -> Self {
+        let coeffs = lhs
+            .coeffs
+            .iter()
+            .enumerate()
+            .map(|(i, x)| Fq2Target::select(builder, x, &rhs.coeffs[i], flag))
+            .collect_vec()
+            .try_into()
+            .unwrap();
+        Fq6Target { coeffs }
+    }
+
+    pub fn constant(builder: &mut CircuitBuilder<F, D>, c: Fq6) -> Self {
+        let coeffs = [c.c0, c.c1, c.c2]
+            .iter()
+            .map(|x| Fq2Target::constant(builder, x.clone()))
+            .collect_vec()
+            .try_into()
+            .unwrap();
+        Self { coeffs }
+    }
+
+    pub fn add(&self, builder: &mut CircuitBuilder<F, D>, rhs: &Self) -> Self {
+        let coeffs = self
+            .coeffs
+            .iter()
+            .enumerate()
+            .map(|(i, x)| x.add(builder, &rhs.coeffs[i]))
+            .collect_vec()
+            .try_into()
+            .unwrap();
+        Fq6Target { coeffs }
+    }
+
+    pub fn neg(&self, builder: &mut CircuitBuilder<F, D>) -> Self {
+        let coeffs = self
+            .coeffs
+            .iter()
+            .map(|x| x.neg(builder))
+            .collect_vec()
+            .try_into()
+            .unwrap();
+        Fq6Target { coeffs }
+    }
+
+    pub fn sub(&self, builder: &mut CircuitBuilder<F, D>, rhs: &Self) -> Self {
+        let coeffs = self
+            .coeffs
+            .iter()
+            .enumerate()
+            .map(|(i, x)| x.sub(builder, &rhs.coeffs[i]))
+            .collect_vec()
+            .try_into()
+            .unwrap();
+        Fq6Target { coeffs }
+    }
+
+    // schoolbook product, folding v^3 = 9 + u:
+    // c0 = a0 b0 + xi (a1 b2 + a2 b1), c1 = a0 b1 + a1 b0 + xi a2 b2, c2 = a0 b2 + a1 b1 + a2 b0
+    pub fn mul(&self, builder: &mut CircuitBuilder<F, D>, rhs: &Self) -> Self {
+        let [a0, a1, a2] = &self.coeffs;
+        let [b0, b1, b2] = &rhs.coeffs;
+        let a0b0 = a0.mul(builder, b0);
+        let a0b1 = a0.mul(builder, b1);
+        let a0b2 = a0.mul(builder, b2);
+        let a1b0 = a1.mul(builder, b0);
+        let a1b1 = a1.mul(builder, b1);
+        let a1b2 = a1.mul(builder, b2);
+        let a2b0 = a2.mul(builder, b0);
+        let a2b1 = a2.mul(builder, b1);
+        let a2b2 = a2.mul(builder, b2);
+
+        let high0 = a1b2.add(builder, &a2b1);
+        let high0 = high0.mul_by_nonresidue(builder);
+        let c0 = a0b0.add(builder, &high0);
+        let high1 = a2b2.mul_by_nonresidue(builder);
+        let c1 = a0b1.add(builder, &a1b0);
+        let c1 = c1.add(builder, &high1);
+        let c2 = a0b2.add(builder, &a1b1);
+        let c2 = c2.add(builder, &a2b0);
+        Fq6Target {
+            coeffs: [c0, c1, c2],
+        }
+    }
+
+    // Karatsuba over the cubic extension (as arkworks' Fq6 mul): 6 Fq2 muls instead of 9,
+    // each itself a 3-mul Fq2Target::mul_karatsuba
+    pub fn mul_karatsuba(&self, builder: &mut CircuitBuilder<F, D>, rhs: &Self) -> Self {
+        let [a0, a1, a2] = &self.coeffs;
+        let [b0, b1, b2] = &rhs.coeffs;
+        let a0b0 = a0.mul_karatsuba(builder, b0);
+        let a1b1 = a1.mul_karatsuba(builder, b1);
+        let a2b2 = a2.mul_karatsuba(builder, b2);
+
+        // a1 b2 + a2 b1 = (a1 + a2)(b1 + b2) - a1 b1 - a2 b2
+        let a1_plus_a2 = a1.add(builder, a2);
+        let b1_plus_b2 = b1.add(builder, b2);
+        let high0 = a1_plus_a2.mul_karatsuba(builder, &b1_plus_b2);
+        let high0 = high0.sub(builder, &a1b1);
+        let high0 = high0.sub(builder, &a2b2);
+        let high0 = high0.mul_by_nonresidue(builder);
+        let c0 = a0b0.add(builder, &high0);
+
+        // a0 b1 + a1 b0 = (a0 + a1)(b0 + b1) - a0 b0 - a1 b1
+        let a0_plus_a1 = a0.add(builder, a1);
+        let b0_plus_b1 = b0.add(builder, b1);
+        let c1 = a0_plus_a1.mul_karatsuba(builder, &b0_plus_b1);
+        let c1 = c1.sub(builder, &a0b0);
+        let c1 = c1.sub(builder, &a1b1);
+        let high1 = a2b2.mul_by_nonresidue(builder);
+        let c1 = c1.add(builder, &high1);
+
+        // a0 b2 + a2 b0 = (a0 + a2)(b0 + b2) - a0 b0 - a2 b2
+        let a0_plus_a2 = a0.add(builder, a2);
+        let b0_plus_b2 = b0.add(builder, b2);
+        let c2 = a0_plus_a2.mul_karatsuba(builder, &b0_plus_b2);
+        let c2 = c2.sub(builder, &a0b0);
+        let c2 = c2.sub(builder, &a2b2);
+        let c2 = c2.add(builder, &a1b1);
+        Fq6Target {
+            coeffs: [c0, c1, c2],
+        }
+    }
+
+    // multiplies by v: (a0, a1, a2) -> (xi a2, a0, a1), as v^3 = xi
+    pub fn mul_by_nonresidue(&self, builder: &mut CircuitBuilder<F, D>) -> Self {
+        let [a0, a1, a2] = &self.coeffs;
+        let r0 = a2.mul_by_nonresidue(builder);
+        Fq6Target {
+            coeffs: [r0, a0.clone(), a1.clone()],
+        }
+    }
+
+    // multiplies by the sparse element c0 + c1 v: 6 Fq2 muls instead of 9
+    pub fn mul_by_01(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        c0: &Fq2Target<F, D>,
+        c1: &Fq2Target<F, D>,
+    ) -> Self {
+        let [a0, a1, a2] = &self.coeffs;
+        let a0c0 = a0.mul(builder, c0);
+        let a0c1 = a0.mul(builder, c1);
+        let a1c0 = a1.mul(builder, c0);
+        let a1c1 = a1.mul(builder, c1);
+        let a2c0 = a2.mul(builder, c0);
+        let a2c1 = a2.mul(builder, c1);
+
+        let high = a2c1.mul_by_nonresidue(builder);
+        let r0 = a0c0.add(builder, &high);
+        let r1 = a0c1.add(builder, &a1c0);
+        let r2 = a1c1.add(builder, &a2c0);
+        Fq6Target {
+            coeffs: [r0, r1, r2],
+        }
+    }
+
+    // multiplies by the sparse element c1 v, which shifts the coefficients up by one
+    pub fn mul_by_1(&self, builder: &mut CircuitBuilder<F, D>, c1: &Fq2Target<F, D>) -> Self {
+        let [a0, a1, a2] = &self.coeffs;
+        let a2c1 = a2.mul(builder, c1);
+        let r0 = a2c1.mul_by_nonresidue(builder);
+        let r1 = a0.mul(builder, c1);
+        let r2 = a1.mul(builder, c1);
+        Fq6Target {
+            coeffs: [r0, r1, r2],
+        }
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Fq6Target<F, D> {
+    pub fn to_vec(&self) -> Vec<Target> {
+        self.coeffs.iter().flat_map(|c| c.to_vec()).collect()
+    }
+
+    pub fn from_vec(builder: &mut CircuitBuilder<F, D>, input: &[Target]) -> Self {
+        let num_limbs = FqTarget::<F, D>::NUM_LIMBS;
+        assert_eq!(
+            input.len(),
+            6 * num_limbs,
+            "Fq6Target::from_vec expects 6 coefficients of {num_limbs} limbs"
+        );
+        let coeffs = input
+            .iter()
+            .cloned()
+            .chunks(2 * num_limbs)
+            .into_iter()
+            .map(|chunk| Fq2Target::from_vec(builder, &chunk.collect_vec()))
+            .collect_vec();
+        Fq6Target {
+            coeffs: coeffs.try_into().unwrap(),
+        }
+    }
+
+    pub fn set_witness<W: WitnessWrite<F>>(&self, pw: &mut W, value: &Fq6) {
+        let coeffs: Vec<Fq2> = vec![value.c0, value.c1, value.c2];
+        self.coeffs
+            .iter()
+            .cloned()
+            .zip(coeffs)
+            .map(|(c_t, c)| c_t.set_witness(pw, &c))
+            .for_each(drop);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bn254::{Fq2, Fq6};
+    use ark_ff::Field;
+    use ark_std::{UniformRand, Zero};
+    use plonky2::{
+        field::goldilocks_field::GoldilocksField,
+        iop::witness::PartialWitness,
+        plonk::{
+            circuit_builder::CircuitBuilder, circuit_data::CircuitConfig,
+            config::PoseidonGoldilocksConfig,
+        },
+    };
+
+    use super::Fq6Target;
+    use crate::fields::fq2_target::Fq2Target;
+
+    type F = GoldilocksField;
+    type C = PoseidonGoldilocksConfig;
+    const D: usize = 2;
+
+    #[test]
+    fn test_fq6_add_sub() {
+        let rng = &mut rand::thread_rng();
+        let a = Fq6::rand(rng);
+        let b = Fq6::rand(rng);
+        let sum_expected = a + b;
+        let diff_expected = a - b;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let a_t = Fq6Target::constant(&mut builder, a);
+        let b_t = Fq6Target::constant(&mut builder, b);
+        let sum_t = a_t.add(&mut builder, &b_t);
+        let diff_t = a_t.sub(&mut builder, &b_t);
+        let sum_expected_t = Fq6Target::constant(&mut builder, sum_expected);
+        let diff_expected_t = Fq6Target::constant(&mut builder, diff_expected);
+
+        Fq6Target::connect(&mut builder, &sum_t, &sum_expected_t);
+        Fq6Target::connect(&mut builder, &diff_t, &diff_expected_t);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw);
+    }
+
+    #[test]
+    fn test_fq6_mul() {
+        let rng = &mut rand::thread_rng();
+        let a = Fq6::rand(rng);
+        let b = Fq6::rand(rng);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let a_t = Fq6Target::empty(&mut builder);
+        let b_t = Fq6Target::empty(&mut builder);
+        let output_t = a_t.mul(&mut builder, &b_t);
+        let expected_t = Fq6Target::constant(&mut builder, a * b);
+        Fq6Target::connect(&mut builder, &output_t, &expected_t);
+
+        let mut pw = PartialWitness::new();
+        a_t.set_witness(&mut pw, &a);
+        b_t.set_witness(&mut pw, &b);
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_fq6_mul_karatsuba() {
+        let rng = &mut rand::thread_rng();
+        let a = Fq6::rand(rng);
+        let b = Fq6::rand(rng);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let a_t = Fq6Target::empty(&mut builder);
+        let b_t = Fq6Target::empty(&mut builder);
+        let output_t = a_t.mul_karatsuba(&mut builder, &b_t);
+        let expected_t = Fq6Target::constant(&mut builder, a * b);
+        Fq6Target::connect(&mut builder, &output_t, &expected_t);
+        let shifted_t = a_t.mul_by_nonresidue(&mut builder);
+        let v = Fq6::new(Fq2::ZERO, Fq2::ONE, Fq2::ZERO);
+        let shifted_expected_t = Fq6Target::constant(&mut builder, a * v);
+        Fq6Target::connect(&mut builder, &shifted_t, &shifted_expected_t);
+
+        let mut pw = PartialWitness::new();
+        a_t.set_witness(&mut pw, &a);
+        b_t.set_witness(&mut pw, &b);
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_fq6_mul_by_01_and_1() {
+        let rng = &mut rand::thread_rng();
+        let a = Fq6::rand(rng);
+        let c0 = Fq2::rand(rng);
+        let c1 = Fq2::rand(rng);
+        let mut expected_01 = a;
+        expected_01.mul_by_01(&c0, &c1);
+        let mut expected_1 = a;
+        expected_1.mul_by_1(&c1);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let a_t = Fq6Target::empty(&mut builder);
+        let c0_t = Fq2Target::empty(&mut builder);
+        let c1_t = Fq2Target::empty(&mut builder);
+        let zero = Fq2Target::constant(&mut builder, Fq2::zero());
+
+        let output_01_t = a_t.mul_by_01(&mut builder, &c0_t, &c1_t);
+        let dense_01_t = Fq6Target::new(vec![c0_t.clone(), c1_t.clone(), zero.clone()]);
+        let dense_output_01_t = a_t.mul(&mut builder, &dense_01_t);
+        Fq6Target::connect(&mut builder, &output_01_t, &dense_output_01_t);
+        let expected_01_t = Fq6Target::constant(&mut builder, expected_01);
+        Fq6Target::connect(&mut builder, &output_01_t, &expected_01_t);
+
+        let output_1_t = a_t.mul_by_1(&mut builder, &c1_t);
+        let dense_1_t = Fq6Target::new(vec![zero.clone(), c1_t.clone(), zero]);
+        let dense_output_1_t = a_t.mul(&mut builder, &dense_1_t);
+        Fq6Target::connect(&mut builder, &output_1_t, &dense_output_1_t);
+        let expected_1_t = Fq6Target::constant(&mut builder, expected_1);
+        Fq6Target::connect(&mut builder, &output_1_t, &expected_1_t);
+
+        let mut pw = PartialWitness::new();
+        a_t.set_witness(&mut pw, &a);
+        c0_t.set_witness(&mut pw, &c0);
+        c1_t.set_witness(&mut pw, &c1);
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "Fq6Target::from_vec expects 6 coefficients of 8 limbs")]
+    fn test_fq6_from_vec_rejects_wrong_length() {
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let input = builder.add_virtual_targets(6 * 8 - 1);
+        Fq6Target::from_vec(&mut builder, &input);
+    }
+}
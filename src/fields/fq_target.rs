@@ -1,4 +1,5 @@
 use ark_bn254::Fq;
+use ark_ff::PrimeField;
 use itertools::Itertools;
 use num::{One, Zero};
 use num_bigint::BigUint;
@@ -11,13 +12,13 @@ use plonky2::{
         witness::{PartitionWitness, Witness, WitnessWrite},
     },
     plonk::{circuit_builder::CircuitBuilder, circuit_data::CommonCircuitData},
-    util::serialization::{Buffer, IoError},
+    util::serialization::{Buffer, IoError, Read, Write},
 };
 use plonky2_crypto::u32::gadgets::{
     arithmetic_u32::U32Target, range_check::range_check_u32_circuit,
 };
 use plonky2_ecdsa::gadgets::{
-    biguint::{BigUintTarget, GeneratedValuesBigUint, WitnessBigUint},
+    biguint::{BigUintTarget, CircuitBuilderBiguint, GeneratedValuesBigUint, WitnessBigUint},
     nonnative::{CircuitBuilderNonNative, NonNativeTarget},
 };
 use std::marker::PhantomData;
@@ -36,6 +37,9 @@ pub struct FqTarget<F: RichField + Extendable<D>, const D: usize> {
 }
 
 impl<F: RichField + Extendable<D>, const D: usize> FqTarget<F, D> {
+    // u32 limbs of a 254-bit nonnative element, the layout of to_vec / from_vec
+    pub const NUM_LIMBS: usize = 8;
+
     pub fn empty(builder: &mut CircuitBuilder<F, D>) -> Self {
         let target = builder.add_virtual_nonnative_target();
         Self {
@@ -72,12 +76,12 @@ impl<F: RichField + Extendable<D>, const D: usize> FqTarget<F, D> {
 
     pub fn to_limbs(&self, builder: &mut CircuitBuilder<F, D>) -> [Target; 8] {
         let mut limbs = self.to_limbs_without_pad();
-        limbs.extend(vec![builder.zero(); 8 - limbs.len()]);
+        limbs.extend(vec![builder.zero(); Self::NUM_LIMBS - limbs.len()]);
         limbs.try_into().unwrap()
     }
 
     pub fn num_limbs() -> usize {
-        8
+        Self::NUM_LIMBS
     }
 
     pub fn new(value: NonNativeTarget<Bn254Base>) -> Self {
@@ -91,6 +95,36 @@ impl<F: RichField + Extendable<D>, const D: usize> FqTarget<F, D> {
         builder.connect_nonnative(&lhs.target, &rhs.target);
     }
 
+    // self = c mod p with copy constraints to constant limbs. This is a completeness
+    // extension of connect to a constant, not a cheaper path: connect_nonnative is only copy
+    // constraints too, so a nonzero c costs the same, and c = 0 costs an is_equal and a
+    // mul_const per limb more. What it adds is that zero, which nonnative results (bounded
+    // by <= p) may carry as 0 or p, accepts both: the limbs are constrained to s * p for a
+    // boolean s. Unreduced inputs (from_vec) congruent to c but above p are rejected, as
+    // with connect
+    pub fn connect_to_constant(&self, builder: &mut CircuitBuilder<F, D>, c: &Fq) {
+        let limbs = self.to_limbs(builder);
+        let digits = |x: BigUint| {
+            let mut digits = x.to_u32_digits();
+            digits.resize(Self::NUM_LIMBS, 0);
+            digits
+        };
+        if c.is_zero() {
+            let p_digits = digits(Fq::MODULUS.into());
+            let p0 = builder.constant(F::from_canonical_u32(p_digits[0]));
+            let is_p = builder.is_equal(limbs[0], p0);
+            for (limb, digit) in limbs.iter().zip(p_digits) {
+                let expected = builder.mul_const(F::from_canonical_u32(digit), is_p.target);
+                builder.connect(*limb, expected);
+            }
+        } else {
+            for (limb, digit) in limbs.iter().zip(digits((*c).into())) {
+                let expected = builder.constant(F::from_canonical_u32(digit));
+                builder.connect(*limb, expected);
+            }
+        }
+    }
+
     pub fn select(
         builder: &mut CircuitBuilder<F, D>,
         a: &Self,
@@ -137,6 +171,53 @@ impl<F: RichField + Extendable<D>, const D: usize> FqTarget<F, D> {
         self.is_equal(builder, &zero)
     }
 
+    // the representative in [0, p). from_vec does not reduce and nonnative results are
+    // only bounded by <= p, so limbs of congruent values can differ: reduce, then map p to 0
+    pub fn reduce(&self, builder: &mut CircuitBuilder<F, D>) -> Self {
+        let reduced = Self {
+            target: builder.reduce(&self.target.value),
+            _marker: PhantomData,
+        };
+        let modulus: BigUint = Fq::MODULUS.into();
+        let modulus = builder.constant_biguint(&modulus);
+        let modulus = Self {
+            target: builder.biguint_to_nonnative(&modulus),
+            _marker: PhantomData,
+        };
+        let is_modulus = reduced.is_equal(builder, &modulus);
+        let zero = Self::zero(builder);
+        Self::select(builder, &zero, &reduced, &is_modulus)
+    }
+
+    // reduces a BigUintTarget of any width (e.g. wide hash output) mod p, constraining
+    // wide = q * p + s with 0 <= s < p
+    pub fn reduce_from_biguint_target(
+        builder: &mut CircuitBuilder<F, D>,
+        wide: &BigUintTarget,
+    ) -> Self {
+        let target = builder.reduce::<Bn254Base>(wide);
+        // the remainder check inside reduce is s <= p, so s = p is excluded here
+        let modulus: BigUint = Fq::MODULUS.into();
+        let modulus_limbs = modulus.to_u32_digits();
+        assert_eq!(target.value.limbs.len(), modulus_limbs.len());
+        let terms = target
+            .value
+            .limbs
+            .iter()
+            .zip(modulus_limbs)
+            .map(|(limb, m)| {
+                let m = builder.constant(F::from_canonical_u32(m));
+                builder.is_equal(limb.0, m).target
+            })
+            .collect_vec();
+        let is_modulus = builder.mul_many(terms);
+        builder.assert_zero(is_modulus);
+        Self {
+            target,
+            _marker: PhantomData,
+        }
+    }
+
     pub fn constant(builder: &mut CircuitBuilder<F, D>, c: Fq) -> Self {
         let target = builder.constant_nonnative(c.into());
         Self {
@@ -182,6 +263,8 @@ impl<F: RichField + Extendable<D>, const D: usize> FqTarget<F, D> {
     }
 
     pub fn mul(&self, builder: &mut CircuitBuilder<F, D>, rhs: &Self) -> Self {
+        #[cfg(feature = "metrics")]
+        crate::fields::metrics::record_mul();
         let target = builder.mul_nonnative(&self.target, &rhs.target);
         Self {
             target,
@@ -189,9 +272,25 @@ impl<F: RichField + Extendable<D>, const D: usize> FqTarget<F, D> {
         }
     }
 
+    // 0, 1 and -1 need no nonnative multiplication
     pub fn mul_const(&self, builder: &mut CircuitBuilder<F, D>, c: &Fq) -> Self {
+        if c.is_zero() {
+            return Self::zero(builder);
+        }
+        if c.is_one() {
+            return self.clone();
+        }
+        if (-*c).is_one() {
+            return self.neg(builder);
+        }
+        #[cfg(feature = "metrics")]
+        crate::fields::metrics::record_const_mul();
         let c = FqTarget::constant(builder, *c);
-        self.mul(builder, &c)
+        let target = builder.mul_nonnative(&self.target, &c.target);
+        Self {
+            target,
+            _marker: PhantomData,
+        }
     }
 
     pub fn inv(&self, builder: &mut CircuitBuilder<F, D>) -> Self {
@@ -214,6 +313,16 @@ impl<F: RichField + Extendable<D>, const D: usize> FqTarget<F, D> {
         bits[0]
     }
 
+    // returns self <= (p - 1) / 2, i.e. self < p / 2: exactly one of y, -y passes for y != 0.
+    // This is the "lexicographically smallest" sign, unlike sgn0 which is the parity.
+    // The limbs are compared as they are, so self must already be reduced (see reduce):
+    // y + p fits in the limbs as well and is never canonical
+    pub fn is_canonical_sign(&self, builder: &mut CircuitBuilder<F, D>) -> BoolTarget {
+        let half: BigUint = Fq::MODULUS_MINUS_ONE_DIV_TWO.into();
+        let half = builder.constant_biguint(&half);
+        builder.cmp_biguint(&self.target.value, &half)
+    }
+
     // if self is not square, this fails
     // the return value is ensured to be sgn0(x) = sgn0(sgn)
     pub fn sqrt_with_sgn(&self, builder: &mut CircuitBuilder<F, D>, sgn: BoolTarget) -> Self {
@@ -264,6 +373,43 @@ impl<F: RichField + Extendable<D>, const D: usize> FqTarget<F, D> {
         res
     }
 
+    // (root, is_square) without failing on non-squares. 3 is not a square in Fq, so
+    // exactly one of self and 3 * self is a square for self != 0, and root^2 equals the one
+    // selected by is_square: that proves the flag either way. Zero is a square, so a false
+    // flag is rejected when self is zero. The root is reduced and of canonical sign, which
+    // makes it unique
+    pub fn sqrt(&self, builder: &mut CircuitBuilder<F, D>) -> (Self, BoolTarget) {
+        let root = Self::empty(builder);
+        let is_square = builder.add_virtual_bool_target_safe();
+        builder.add_simple_generator(FqSqrtFlagGenerator::<F, D> {
+            x: self.clone(),
+            root: root.clone(),
+            is_square,
+        });
+
+        let three_x = self.mul_const(builder, &Fq::from(3));
+        let square = Self::select(builder, self, &three_x, &is_square);
+        let root_squared = root.mul(builder, &root);
+        Self::connect(builder, &root_squared, &square);
+        let root = root.reduce(builder);
+        let is_canonical = root.is_canonical_sign(builder);
+        builder.assert_one(is_canonical.target);
+
+        let is_zero = self.reduce(builder).is_zero(builder);
+        let not_square = builder.not(is_square);
+        let zero_not_square = builder.and(is_zero, not_square);
+        builder.assert_zero(zero_not_square.target);
+        (root, is_square)
+    }
+
+    // proves self has a square root without exposing it. Cheaper than is_square, but
+    // proving fails (instead of returning false) when self is not a square
+    pub fn assert_is_square(&self, builder: &mut CircuitBuilder<F, D>) {
+        // every nonzero square has a root of each sign, so fixing sgn0 = 0 loses nothing
+        let sgn = builder._false();
+        let _sqrt = self.sqrt_with_sgn(builder, sgn);
+    }
+
     pub fn legendre(&self, builder: &mut CircuitBuilder<F, D>) -> Self {
         let k: BigUint = (Fq::from(-1) / Fq::from(2)).into();
         self.pow(builder, k.to_u64_digits())
@@ -282,7 +428,12 @@ impl<F: RichField + Extendable<D>, const D: usize> FqTarget<F, D> {
     }
 
     pub fn from_vec(builder: &mut CircuitBuilder<F, D>, input: &[Target]) -> Self {
-        assert_eq!(input.len(), 8);
+        assert_eq!(
+            input.len(),
+            Self::NUM_LIMBS,
+            "FqTarget::from_vec expects {} u32 limbs",
+            Self::NUM_LIMBS
+        );
         let limbs = input.iter().cloned().map(|a| U32Target(a)).collect_vec();
         range_check_u32_circuit(builder, limbs.clone());
         let biguint = BigUintTarget { limbs };
@@ -330,7 +481,7 @@ impl<F: RichField + Extendable<D>, const D: usize> FqTarget<F, D> {
 }
 
 #[derive(Debug)]
-struct FqSqrtGenerator<F: RichField + Extendable<D>, const D: usize> {
+pub struct FqSqrtGenerator<F: RichField + Extendable<D>, const D: usize> {
     x: FqTarget<F, D>,
     sgn: BoolTarget,
     sqrt: FqTarget<F, D>,
@@ -362,18 +513,85 @@ impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F, D> for FqS
         "FqSqrtGenerator".to_string()
     }
 
-    fn serialize(&self, _: &mut Vec<u8>, _: &CommonCircuitData<F, D>) -> Result<(), IoError> {
-        unimplemented!()
+    fn serialize(
+        &self,
+        dst: &mut Vec<u8>,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Result<(), IoError> {
+        self.x.serialize(dst, common_data)?;
+        self.sqrt.serialize(dst, common_data)?;
+        dst.write_target_bool(self.sgn)
+    }
+
+    fn deserialize(
+        src: &mut Buffer,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Result<Self, IoError> {
+        let x = FqTarget::deserialize(src, common_data)?;
+        let sqrt = FqTarget::deserialize(src, common_data)?;
+        let sgn = src.read_target_bool()?;
+
+        Ok(Self { x, sgn, sqrt })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct FqSqrtFlagGenerator<F: RichField + Extendable<D>, const D: usize> {
+    x: FqTarget<F, D>,
+    root: FqTarget<F, D>,
+    is_square: BoolTarget,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F, D>
+    for FqSqrtFlagGenerator<F, D>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        self.x.target.value.limbs.iter().map(|&l| l.0).collect_vec()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        use ark_ff::Field as ArkField;
+        let x = from_biguint_to_fq(witness.get_biguint_target(self.x.target.value.clone()));
+        let (root, is_square) = match x.sqrt() {
+            Some(root) => (root, true),
+            None => ((x * Fq::from(3)).sqrt().unwrap(), false),
+        };
+        // the root of canonical sign, see is_canonical_sign
+        let root: BigUint = root.min(-root).into();
+        out_buffer.set_biguint_target(&self.root.target.value, &root);
+        out_buffer.set_bool_target(self.is_square, is_square);
+    }
+
+    fn id(&self) -> std::string::String {
+        "FqSqrtFlagGenerator".to_string()
+    }
+
+    fn serialize(
+        &self,
+        dst: &mut Vec<u8>,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Result<(), IoError> {
+        self.x.serialize(dst, common_data)?;
+        self.root.serialize(dst, common_data)?;
+        dst.write_target_bool(self.is_square)
     }
-    fn deserialize(_: &mut Buffer, _: &CommonCircuitData<F, D>) -> Result<Self, IoError> {
-        unimplemented!()
+
+    fn deserialize(
+        src: &mut Buffer,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Result<Self, IoError> {
+        let x = FqTarget::deserialize(src, common_data)?;
+        let root = FqTarget::deserialize(src, common_data)?;
+        let is_square = src.read_target_bool()?;
+
+        Ok(Self { x, root, is_square })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use ark_bn254::Fq;
-    use ark_ff::Field;
+    use ark_ff::{Field, PrimeField};
     use ark_std::UniformRand;
     use ark_std::Zero;
 
@@ -385,6 +603,7 @@ mod tests {
             config::PoseidonGoldilocksConfig,
         },
     };
+    use num_bigint::BigUint;
     use rand::Rng;
 
     use crate::fields::native::sgn0_fq;
@@ -395,6 +614,47 @@ mod tests {
     type C = PoseidonGoldilocksConfig;
     const D: usize = 2;
 
+    fn check_sqrt(x: Fq) {
+        // the root <= (p - 1) / 2
+        let expected = x.sqrt().map(|root| root.min(-root));
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = FqTarget::empty(&mut builder);
+        let (root_t, is_square_t) = x_t.sqrt(&mut builder);
+        let expected_is_square_t = builder.constant_bool(expected.is_some());
+        builder.connect(is_square_t.target, expected_is_square_t.target);
+        if let Some(root) = expected {
+            let root_expected_t = FqTarget::constant(&mut builder, root);
+            FqTarget::connect(&mut builder, &root_t, &root_expected_t);
+        }
+
+        let mut pw = PartialWitness::new();
+        x_t.set_witness(&mut pw, &x);
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_sqrt() {
+        let rng = &mut rand::thread_rng();
+        let r = Fq::rand(rng);
+        check_sqrt(r * r);
+        check_sqrt(Fq::zero());
+        let non_residue = loop {
+            let x = Fq::rand(rng);
+            if x.legendre().is_qnr() {
+                break x;
+            }
+        };
+        check_sqrt(non_residue);
+    }
+
+    #[test]
+    fn test_three_is_not_a_square() {
+        assert!(Fq::from(3).legendre().is_qnr());
+    }
+
     #[test]
     fn test_from_to_vec() {
         let rng = &mut rand::thread_rng();
@@ -479,6 +739,40 @@ mod tests {
         let _proof = data.prove(pw);
     }
 
+    // value + k p written straight into the limbs, as nonnative results or from_vec inputs
+    // can hold
+    fn check_reduce(value: Fq, k: u32) {
+        let p: BigUint = Fq::MODULUS.into();
+        let unreduced = BigUint::from(value) + p * k;
+        let mut limbs = unreduced.to_u32_digits();
+        limbs.resize(FqTarget::<F, D>::NUM_LIMBS, 0);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = FqTarget::empty(&mut builder);
+        let reduced_t = x_t.reduce(&mut builder);
+        let expected_t = FqTarget::constant(&mut builder, value);
+        FqTarget::connect(&mut builder, &reduced_t, &expected_t);
+
+        let mut pw = PartialWitness::new();
+        for (l_t, l) in x_t.target.value.limbs.iter().zip(limbs) {
+            pw.set_target(l_t.0, F::from_canonical_u32(l));
+        }
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_reduce() {
+        let a = Fq::rand(&mut rand::thread_rng());
+        check_reduce(a, 0);
+        check_reduce(a, 1);
+        check_reduce(a, 2);
+        // p itself reduces to 0
+        check_reduce(Fq::zero(), 1);
+        check_reduce(Fq::zero(), 0);
+    }
+
     #[test]
     fn test_sign0() {
         let a = Fq::from(5);
@@ -522,6 +816,34 @@ mod tests {
         dbg!(data.common.degree_bits());
     }
 
+    #[test]
+    fn test_is_canonical_sign() {
+        let half = Fq::from_bigint(Fq::MODULUS_MINUS_ONE_DIV_TWO).unwrap();
+        let y = Fq::rand(&mut rand::thread_rng());
+        let cases = [
+            (Fq::zero(), true),
+            (Fq::from(1u64), true),
+            (half, true),
+            (half + Fq::from(1u64), false),
+            (-Fq::from(1u64), false),
+            (y, y <= half),
+            (-y, -y <= half),
+        ];
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        for (a, expected) in cases {
+            let a_t = FqTarget::constant(&mut builder, a);
+            let is_canonical = a_t.is_canonical_sign(&mut builder);
+            let expected_t = builder.constant_bool(expected);
+            builder.connect(is_canonical.target, expected_t.target);
+        }
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
     #[test]
     fn test_sqrt_with_sgn() {
         let rng = &mut rand::thread_rng();
@@ -553,4 +875,37 @@ mod tests {
         let data = builder.build::<C>();
         let _proof = data.prove(pw);
     }
+
+    #[test]
+    fn test_assert_is_square() {
+        let rng = &mut rand::thread_rng();
+        let r = Fq::rand(rng);
+        let x = r * r;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = FqTarget::constant(&mut builder, x);
+        x_t.assert_is_square(&mut builder);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_assert_is_square_rejects_non_residue() {
+        // p = 3 mod 4, so -1 is not a square
+        let x = -Fq::from(1u64);
+        assert!(x.sqrt().is_none());
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = FqTarget::constant(&mut builder, x);
+        x_t.assert_is_square(&mut builder);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw);
+    }
 }
@@ -1,4 +1,5 @@
 use ark_bn254::Fr;
+use ark_ff::PrimeField;
 use itertools::Itertools;
 use num::Zero;
 use num_bigint::BigUint;
@@ -73,10 +74,86 @@ impl<F: RichField + Extendable<D>, const D: usize> FrTarget<F, D> {
         8
     }
 
+    // little-endian bits; each limb is split with a base-2 BaseSumGate, which range checks
+    // every digit, so the returned targets are constrained to be boolean
     pub fn to_bits(&self, builder: &mut CircuitBuilder<F, D>) -> Vec<BoolTarget> {
         builder.split_nonnative_to_bits(&self.target)
     }
 
+    // recomposes little-endian bits into a scalar mod r. Every bit is asserted boolean:
+    // otherwise a prover could e.g. put 2 in bit i instead of 1 in bit i + 1 and forge
+    // a different decomposition of the same value
+    pub fn from_bits(builder: &mut CircuitBuilder<F, D>, bits: &[BoolTarget]) -> Self {
+        assert!(bits.len() <= 32 * Self::num_limbs());
+        let biguint = Self::bits_to_biguint_target(builder, bits);
+        let target = builder.reduce(&biguint);
+        Self {
+            target,
+            _marker: PhantomData,
+        }
+    }
+
+    // packs little-endian bits into u32 limbs, asserting each bit boolean
+    fn bits_to_biguint_target(
+        builder: &mut CircuitBuilder<F, D>,
+        bits: &[BoolTarget],
+    ) -> BigUintTarget {
+        let limbs = bits
+            .chunks(32)
+            .map(|chunk| {
+                let mut limb = builder.zero();
+                for (i, bit) in chunk.iter().enumerate() {
+                    builder.assert_bool(*bit);
+                    limb = builder.mul_const_add(F::from_canonical_u64(1 << i), bit.target, limb);
+                }
+                U32Target(limb)
+            })
+            .collect_vec();
+        BigUintTarget { limbs }
+    }
+
+    // a 256-bit hash (little-endian bits) reduced mod r into a canonical scalar. The result
+    // is biased: 2^256 = 5r + s with s ~ 0.29r, so the s smallest scalars have 6 preimages
+    // and the others 5, a statistical distance of about 0.039 (~2^-4.7) from uniform. Where
+    // near-uniform scalars matter, reduce 384 or more hash bits with
+    // reduce_from_biguint_target instead, which brings the distance below 2^-128
+    pub fn from_hash_reduce(
+        builder: &mut CircuitBuilder<F, D>,
+        hash_bits: &[BoolTarget; 256],
+    ) -> Self {
+        let wide = Self::bits_to_biguint_target(builder, hash_bits);
+        Self::reduce_from_biguint_target(builder, &wide)
+    }
+
+    // reduces a BigUintTarget of any width (e.g. a hash output used as a Fiat-Shamir
+    // challenge) mod r, constraining wide = q * r + s with 0 <= s < r
+    pub fn reduce_from_biguint_target(
+        builder: &mut CircuitBuilder<F, D>,
+        wide: &BigUintTarget,
+    ) -> Self {
+        let target = builder.reduce::<Bn254Scalar>(wide);
+        // the remainder check inside reduce is s <= r, so s = r is excluded here
+        let modulus: BigUint = Fr::MODULUS.into();
+        let modulus_limbs = modulus.to_u32_digits();
+        assert_eq!(target.value.limbs.len(), modulus_limbs.len());
+        let terms = target
+            .value
+            .limbs
+            .iter()
+            .zip(modulus_limbs)
+            .map(|(limb, m)| {
+                let m = builder.constant(F::from_canonical_u32(m));
+                builder.is_equal(limb.0, m).target
+            })
+            .collect_vec();
+        let is_modulus = builder.mul_many(terms);
+        builder.assert_zero(is_modulus);
+        Self {
+            target,
+            _marker: PhantomData,
+        }
+    }
+
     pub fn construct(value: NonNativeTarget<Bn254Scalar>) -> Self {
         Self {
             target: value,
@@ -229,15 +306,24 @@ impl<F: RichField + Extendable<D>, const D: usize> FrTarget<F, D> {
 #[cfg(test)]
 mod tests {
     use ark_bn254::Fr;
+    use ark_ff::PrimeField;
+    use ark_std::UniformRand;
+    use num_bigint::{BigUint, RandBigInt};
     use num_traits::*;
     use plonky2::{
-        field::goldilocks_field::GoldilocksField,
-        plonk::{circuit_builder::CircuitBuilder, circuit_data::CircuitConfig},
+        field::{goldilocks_field::GoldilocksField, types::Field},
+        iop::witness::{PartialWitness, WitnessWrite},
+        plonk::{
+            circuit_builder::CircuitBuilder, circuit_data::CircuitConfig,
+            config::PoseidonGoldilocksConfig,
+        },
     };
+    use plonky2_ecdsa::gadgets::biguint::{CircuitBuilderBiguint, WitnessBigUint};
 
     use super::FrTarget;
 
     type F = GoldilocksField;
+    type C = PoseidonGoldilocksConfig;
     const D: usize = 2;
 
     #[test]
@@ -250,4 +336,117 @@ mod tests {
         let bits = x_t.to_bits(&mut builder);
         dbg!(bits.len());
     }
+
+    #[test]
+    fn test_fr_bits_roundtrip() {
+        let rng = &mut rand::thread_rng();
+        let x = Fr::rand(rng);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = FrTarget::constant(&mut builder, x);
+        let bits = x_t.to_bits(&mut builder);
+        let restored_x_t = FrTarget::from_bits(&mut builder, &bits);
+
+        FrTarget::connect(&mut builder, &x_t, &restored_x_t);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw);
+    }
+
+    // without a boolean check, bits = [2, 0, ..] passes as a decomposition of 2
+    #[test]
+    fn test_unconstrained_bits_can_be_forged() {
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let bits = (0..8)
+            .map(|_| builder.add_virtual_bool_target_unsafe())
+            .collect::<Vec<_>>();
+        let mut sum = builder.zero();
+        for (i, bit) in bits.iter().enumerate() {
+            sum = builder.mul_const_add(F::from_canonical_u64(1 << i), bit.target, sum);
+        }
+        let two = builder.constant(F::TWO);
+        builder.connect(sum, two);
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(bits[0].target, F::TWO);
+        for bit in bits.iter().skip(1) {
+            pw.set_target(bit.target, F::ZERO);
+        }
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_from_bits_rejects_forged_bits() {
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let bits = (0..8)
+            .map(|_| builder.add_virtual_bool_target_unsafe())
+            .collect::<Vec<_>>();
+        let x_t = FrTarget::from_bits(&mut builder, &bits);
+        let expected_t = FrTarget::constant(&mut builder, Fr::from(2u64));
+
+        FrTarget::connect(&mut builder, &x_t, &expected_t);
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(bits[0].target, F::TWO);
+        for bit in bits.iter().skip(1) {
+            pw.set_target(bit.target, F::ZERO);
+        }
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw);
+    }
+
+    fn check_reduce_from_biguint_target(num_limbs: usize) {
+        let rng = &mut rand::thread_rng();
+        let wide = rng.gen_biguint(32 * num_limbs as u64);
+        let modulus: BigUint = Fr::MODULUS.into();
+        let expected = Fr::from(&wide % &modulus);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let wide_t = builder.add_virtual_biguint_target(num_limbs);
+        let x_t = FrTarget::reduce_from_biguint_target(&mut builder, &wide_t);
+        let expected_t = FrTarget::constant(&mut builder, expected);
+
+        FrTarget::connect(&mut builder, &x_t, &expected_t);
+
+        let mut pw = PartialWitness::new();
+        pw.set_biguint_target(&wide_t, &wide);
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw);
+    }
+
+    #[test]
+    fn test_reduce_from_biguint_target() {
+        check_reduce_from_biguint_target(8);
+        check_reduce_from_biguint_target(16);
+    }
+
+    #[test]
+    fn test_from_hash_reduce() {
+        // all ones, 2^256 - 1, is above 5r so the reduction actually wraps
+        let hash = (BigUint::one() << 256) - BigUint::one();
+        let modulus: BigUint = Fr::MODULUS.into();
+        let expected = Fr::from(&hash % &modulus);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let hash_bits: [_; 256] = core::array::from_fn(|_| builder.add_virtual_bool_target_safe());
+        let x_t = FrTarget::from_hash_reduce(&mut builder, &hash_bits);
+        let expected_t = FrTarget::constant(&mut builder, expected);
+
+        FrTarget::connect(&mut builder, &x_t, &expected_t);
+
+        let mut pw = PartialWitness::new();
+        for (i, bit) in hash_bits.iter().enumerate() {
+            pw.set_bool_target(*bit, hash.bit(i as u64));
+        }
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
 }
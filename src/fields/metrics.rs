@@ -0,0 +1,102 @@
+use std::cell::Cell;
+
+// nonnative multiplications added to circuits built on this thread, split into
+// variable * variable (FqTarget::mul) and variable * constant (FqTarget::mul_const). The
+// counters are thread local so parallel tests do not see each other's circuits
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MulCount {
+    pub muls: usize,
+    pub const_muls: usize,
+}
+
+thread_local! {
+    static MUL_COUNT: Cell<MulCount> = Cell::new(MulCount::default());
+}
+
+pub fn mul_count() -> MulCount {
+    MUL_COUNT.with(|c| c.get())
+}
+
+pub fn reset_mul_count() {
+    MUL_COUNT.with(|c| c.set(MulCount::default()));
+}
+
+// runs f and returns the multiplications it added, leaving the running total untouched
+pub fn count_muls<R>(f: impl FnOnce() -> R) -> (R, MulCount) {
+    let before = mul_count();
+    let res = f();
+    let after = mul_count();
+    let count = MulCount {
+        muls: after.muls - before.muls,
+        const_muls: after.const_muls - before.const_muls,
+    };
+    (res, count)
+}
+
+pub(crate) fn record_mul() {
+    MUL_COUNT.with(|c| {
+        let mut count = c.get();
+        count.muls += 1;
+        c.set(count);
+    });
+}
+
+pub(crate) fn record_const_mul() {
+    MUL_COUNT.with(|c| {
+        let mut count = c.get();
+        count.const_muls += 1;
+        c.set(count);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2::{
+        field::goldilocks_field::GoldilocksField,
+        plonk::{circuit_builder::CircuitBuilder, circuit_data::CircuitConfig},
+    };
+
+    use crate::fields::fq12_target::Fq12Target;
+
+    use super::{count_muls, mul_count, reset_mul_count};
+
+    type F = GoldilocksField;
+    const D: usize = 2;
+
+    #[test]
+    fn test_fq12_mul_counts() {
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let a = Fq12Target::empty(&mut builder);
+        let b = Fq12Target::empty(&mut builder);
+
+        let (_, mul) = count_muls(|| a.mul(&mut builder, &b));
+        assert_eq!(mul.muls, 144);
+        // xi * (degree 6..10 coefficients), 9 * a per Fq coefficient
+        assert_eq!(mul.const_muls, 10);
+
+        let (_, square) = count_muls(|| a.square(&mut builder));
+        assert_eq!(square.muls, 78);
+        assert_eq!(square.const_muls, 10);
+
+        let (_, karatsuba) = count_muls(|| a.mul_karatsuba(&mut builder, &b));
+        assert_eq!(karatsuba.muls, 54);
+
+        // 3 Fq4 squarings of 2 Fq2 muls each, 4 Fq muls per Fq2 mul; xi is 2 const muls,
+        // twice per Fq4 squaring and once more for t5
+        let (_, cyclotomic) = count_muls(|| a.cyclotomic_square(&mut builder));
+        assert_eq!(cyclotomic.muls, 24);
+        assert_eq!(cyclotomic.const_muls, 8);
+    }
+
+    #[test]
+    fn test_reset_mul_count() {
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let a = Fq12Target::empty(&mut builder);
+        a.mul(&mut builder, &a);
+        assert!(mul_count().muls >= 144);
+        reset_mul_count();
+        assert_eq!(mul_count().muls, 0);
+    }
+}
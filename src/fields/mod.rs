@@ -1,9 +1,21 @@
+#[cfg(feature = "std")]
 pub mod bn254base;
+#[cfg(feature = "std")]
 pub mod bn254scalar;
+#[cfg(feature = "std")]
 pub mod debug_tools;
+#[cfg(feature = "std")]
 pub mod fq12_target;
+#[cfg(feature = "std")]
 pub mod fq2_target;
+#[cfg(feature = "std")]
+pub mod fq6_target;
+#[cfg(feature = "std")]
 pub mod fq_target;
+#[cfg(feature = "std")]
 pub mod fr_target;
+#[cfg(feature = "metrics")]
+pub mod metrics;
 pub mod native;
+#[cfg(feature = "std")]
 pub mod u256_target;
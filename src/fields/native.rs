@@ -1,16 +1,41 @@
-use std::ops::{Add, Mul};
+use alloc::vec::Vec;
+use core::ops::{Add, Mul};
 
 use ark_bn254::{Fq, Fq12, Fq2, Fq6};
-use ark_ff::{BigInt, PrimeField};
-use ark_std::Zero;
+use ark_ff::{BigInt, Field, PrimeField};
+use ark_std::{rand::SeedableRng, One, UniformRand, Zero};
 use num_bigint::BigUint;
+use rand_chacha::ChaCha20Rng;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct MyFq12 {
     pub coeffs: [Fq; 12],
 }
 
-// impl from trait
+impl MyFq12 {
+    // position of flat coefficient i in arkworks' Fq12 as (fq12 c0/c1, fq6 c0/c1/c2,
+    // fq2 c0/c1). coeffs[i] is the coefficient of w^(i % 6) u^(i / 6), and since v = w^2
+    // w^k lives in Fq12 half k % 2 at Fq6 slot k / 2
+    pub fn index_to_tower_position(i: usize) -> (usize, usize, usize) {
+        assert!(i < 12, "MyFq12 has 12 coefficients, got index {i}");
+        let k = i % 6;
+        (k % 2, k / 2, i / 6)
+    }
+
+    // deterministic pseudo-random element: the 12 coefficients drawn in order from ChaCha20
+    // seeded with seed, so a seed names the same element on every machine and run
+    pub fn from_seed(seed: u64) -> Self {
+        let mut rng = ChaCha20Rng::seed_from_u64(seed);
+        let coeffs = core::array::from_fn(|_| Fq::rand(&mut rng));
+        Self { coeffs }
+    }
+}
+
+// flat layout: coeffs[i] is the coefficient of w^(i % 6) u^(i / 6), so
+// [c0.c0.c0, c1.c0.c0, c0.c1.c0, c1.c1.c0, c0.c2.c0, c1.c2.c0,
+//  c0.c0.c1, c1.c0.c1, c0.c1.c1, c1.c1.c1, c0.c2.c1, c1.c2.c1]
+// in arkworks' tower names. index_to_tower_position gives the same mapping per index, and
+// the two From impls are inverse to each other
 impl From<Fq12> for MyFq12 {
     fn from(fq12: Fq12) -> Self {
         let c0: Fq6 = fq12.c0;
@@ -151,11 +176,71 @@ impl Mul for MyFq12 {
     }
 }
 
+// returns gamma_k such that frobenius_map(c_k w^k, power) = frobenius_map(c_k, power) gamma_k w^k
+// for the Fq2 coefficient c_k of w^k, read off by mapping each basis element w^k
+pub fn frobenius_coeffs(power: usize) -> [Fq2; 6] {
+    let mut gammas = [Fq2::zero(); 6];
+    for k in 0..6 {
+        let mut basis = MyFq12 {
+            coeffs: [Fq::zero(); 12],
+        };
+        basis.coeffs[k] = Fq::one();
+        let basis: Fq12 = basis.into();
+        let mapped: MyFq12 = basis.frobenius_map(power).into();
+        gammas[k] = Fq2::new(mapped.coeffs[k], mapped.coeffs[k + 6]);
+    }
+    gammas
+}
+
 pub fn from_biguint_to_fq(x: BigUint) -> Fq {
     let x: BigInt<4> = x.try_into().unwrap();
     Fq::from_bigint(x).unwrap()
 }
 
+// a coefficient of an encoded Fq12 is not a canonical Fq, i.e. it is >= p
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeError {
+    pub index: usize,
+}
+
+impl core::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "coefficient {} is not below the field modulus", self.index)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
+
+pub const FQ12_BYTES: usize = 12 * 32;
+
+// 12 big-endian 32 byte coefficients in MyFq12 order: chunk i holds coeffs[i], the
+// coefficient of w^(i % 6) u^(i / 6), the same layout Fq12Target uses for its limbs
+pub fn fq12_to_bytes(x: &Fq12) -> [u8; FQ12_BYTES] {
+    let x: MyFq12 = (*x).into();
+    let mut bytes = [0u8; FQ12_BYTES];
+    for (chunk, c) in bytes.chunks_mut(32).zip(x.coeffs.iter()) {
+        for (dst, limb) in chunk.chunks_mut(8).zip(c.into_bigint().0.iter().rev()) {
+            dst.copy_from_slice(&limb.to_be_bytes());
+        }
+    }
+    bytes
+}
+
+// inverse of fq12_to_bytes. Each coefficient must be canonical, so every Fq12 has exactly
+// one encoding
+pub fn fq12_from_bytes(bytes: &[u8; FQ12_BYTES]) -> Result<Fq12, DecodeError> {
+    let mut coeffs = [Fq::zero(); 12];
+    for (index, chunk) in bytes.chunks(32).enumerate() {
+        let mut limbs = [0u64; 4];
+        for (limb, src) in limbs.iter_mut().rev().zip(chunk.chunks(8)) {
+            *limb = u64::from_be_bytes(src.try_into().unwrap());
+        }
+        coeffs[index] = Fq::from_bigint(BigInt::new(limbs)).ok_or(DecodeError { index })?;
+    }
+    Ok(MyFq12 { coeffs }.into())
+}
+
 pub fn sgn0_fq(x: Fq) -> bool {
     let y: BigUint = x.into();
     let digits = y.to_u32_digits();
@@ -240,10 +325,14 @@ pub fn get_naf(mut exp: Vec<u64>) -> Vec<i8> {
 
 #[cfg(test)]
 mod tests {
-    use ark_bn254::Fq12;
-    use ark_std::UniformRand;
+    use ark_bn254::{Fq, Fq12};
+    use ark_ff::PrimeField;
+    use ark_std::{One, UniformRand, Zero};
+    use num_bigint::BigUint;
 
-    use crate::fields::native::MyFq12;
+    use crate::fields::native::{
+        fq12_from_bytes, fq12_to_bytes, DecodeError, MyFq12, FQ12_BYTES,
+    };
 
     #[test]
     fn test_myfq12() {
@@ -265,4 +354,84 @@ mod tests {
         assert_eq!(c_expected, c);
         assert_eq!(d_expected, d);
     }
+
+    #[test]
+    fn test_conversion_roundtrip() {
+        let rng = &mut rand::thread_rng();
+        for _ in 0..100 {
+            let x = Fq12::rand(rng);
+            let xm: MyFq12 = x.into();
+            assert_eq!(Fq12::from(xm), x);
+
+            let ym = MyFq12 {
+                coeffs: core::array::from_fn(|_| Fq::rand(rng)),
+            };
+            let y: Fq12 = ym.into();
+            assert_eq!(MyFq12::from(y), ym);
+        }
+    }
+
+    #[test]
+    fn test_index_to_tower_position() {
+        for i in 0..12 {
+            let mut basis = MyFq12 {
+                coeffs: [Fq::zero(); 12],
+            };
+            basis.coeffs[i] = Fq::one();
+            let basis: Fq12 = basis.into();
+            let expected = MyFq12::index_to_tower_position(i);
+            for (a, fq6) in [basis.c0, basis.c1].iter().enumerate() {
+                for (b, fq2) in [fq6.c0, fq6.c1, fq6.c2].iter().enumerate() {
+                    for (c, x) in [fq2.c0, fq2.c1].iter().enumerate() {
+                        let want = if (a, b, c) == expected {
+                            Fq::one()
+                        } else {
+                            Fq::zero()
+                        };
+                        assert_eq!(*x, want, "coefficient {i} at position {:?}", (a, b, c));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_fq12_bytes_roundtrip() {
+        let rng = &mut rand::thread_rng();
+        for _ in 0..20 {
+            let x = Fq12::rand(rng);
+            let bytes = fq12_to_bytes(&x);
+            assert_eq!(fq12_from_bytes(&bytes), Ok(x));
+        }
+
+        // coefficient i is the big-endian value of chunk i
+        let x: Fq12 = MyFq12 {
+            coeffs: core::array::from_fn(|i| Fq::from(i as u64 + 1)),
+        }
+        .into();
+        let bytes = fq12_to_bytes(&x);
+        for i in 0..12 {
+            assert_eq!(bytes[32 * i + 31], i as u8 + 1);
+            assert!(bytes[32 * i..32 * i + 31].iter().all(|b| *b == 0));
+        }
+    }
+
+    #[test]
+    fn test_fq12_from_bytes_rejects_non_canonical() {
+        let modulus: BigUint = Fq::MODULUS.into();
+        let modulus = modulus.to_bytes_be();
+        for index in [0, 5, 11] {
+            let mut bytes = fq12_to_bytes(&Fq12::one());
+            bytes[32 * index..32 * (index + 1)].copy_from_slice(&modulus);
+            assert_eq!(fq12_from_bytes(&bytes), Err(DecodeError { index }));
+        }
+        let bytes = [0xff; FQ12_BYTES];
+        assert_eq!(fq12_from_bytes(&bytes), Err(DecodeError { index: 0 }));
+    }
+
+    #[test]
+    fn test_from_seed() {
+        assert_eq!(MyFq12::from_seed(7), MyFq12::from_seed(7));
+        assert_ne!(MyFq12::from_seed(7), MyFq12::from_seed(8));
+    }
 }
@@ -1,3 +1,12 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod bench;
+#[cfg(feature = "std")]
 pub mod curves;
 pub mod fields;
+#[cfg(feature = "std")]
+pub mod pairing;
 pub mod utils;
@@ -0,0 +1,178 @@
+use ark_bn254::{Fq12, G2Affine};
+use ark_ec::AffineRepr;
+use ark_ff::Field;
+use plonky2::{
+    field::extension::Extendable, hash::hash_types::RichField,
+    plonk::circuit_builder::CircuitBuilder,
+};
+
+use crate::{
+    curves::{g1curve_target::G1Target, g2curve_target::G2Target},
+    fields::fq12_target::Fq12Target,
+    pairing::{multi_miller_loop, PreparedG2Target},
+};
+
+// checks e(partial_sig, g2) == e(message, pubkey_share) for one signer's share, before
+// the partial signatures are combined. message is the already hashed point H(m).
+// Checked as e(partial_sig, g2) * e(-message, pubkey_share) == 1 with a single final
+// exponentiation. The points may come from the witness: pubkey_share is asserted in G2,
+// message and partial_sig on G1, and neither pubkey_share nor message may be the identity,
+// which would make the check hold for the identity signature
+pub fn verify_partial<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    pubkey_share: &G2Target<F, D>,
+    message: &G1Target<F, D>,
+    partial_sig: &G1Target<F, D>,
+) {
+    pubkey_share.assert_in_subgroup(builder);
+    builder.assert_zero(pubkey_share.infinity.target);
+    message.assert_on_curve(builder);
+    builder.assert_zero(message.infinity.target);
+    partial_sig.assert_on_curve(builder);
+    let g2 = PreparedG2Target::constant(builder, G2Affine::generator());
+    let pk = PreparedG2Target::from_g2(builder, pubkey_share);
+    let neg_message = message.neg(builder);
+    let f = multi_miller_loop(builder, &[(partial_sig.clone(), g2), (neg_message, pk)]);
+    let e = f.final_exponentiation(builder);
+    let one = Fq12Target::constant(builder, Fq12::ONE);
+    Fq12Target::connect(builder, &e, &one);
+}
+
+// checks e(sig_i, g2) == e(message_i, pubkey_i) for every (pubkey, message, signature)
+// entry, where each signer may have signed a distinct message. All the checks are batched
+// into one multi Miller loop as prod e(sig_i, g2) * prod e(-message_i, pubkey_i) == 1,
+// followed by a single final exponentiation. Every point is validated as in
+// verify_partial
+pub fn verify_distinct<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    entries: &[(G2Target<F, D>, G1Target<F, D>, G1Target<F, D>)],
+) {
+    assert!(!entries.is_empty(), "verify_distinct needs at least one entry");
+    let g2 = PreparedG2Target::constant(builder, G2Affine::generator());
+    let mut pairs = Vec::with_capacity(2 * entries.len());
+    for (pubkey, message, sig) in entries {
+        pubkey.assert_in_subgroup(builder);
+        builder.assert_zero(pubkey.infinity.target);
+        message.assert_on_curve(builder);
+        builder.assert_zero(message.infinity.target);
+        sig.assert_on_curve(builder);
+        let pk = PreparedG2Target::from_g2(builder, pubkey);
+        let neg_message = message.neg(builder);
+        pairs.push((sig.clone(), g2.clone()));
+        pairs.push((neg_message, pk));
+    }
+    let f = multi_miller_loop(builder, &pairs);
+    let e = f.final_exponentiation(builder);
+    let one = Fq12Target::constant(builder, Fq12::ONE);
+    Fq12Target::connect(builder, &e, &one);
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bn254::{Fr, G1Affine, G2Affine};
+    use ark_ec::{AffineRepr, CurveGroup};
+    use ark_std::UniformRand;
+    use plonky2::{
+        field::goldilocks_field::GoldilocksField,
+        iop::witness::PartialWitness,
+        plonk::{
+            circuit_builder::CircuitBuilder, circuit_data::CircuitConfig,
+            config::PoseidonGoldilocksConfig,
+        },
+    };
+
+    use super::{verify_distinct, verify_partial};
+    use crate::curves::{g1curve_target::G1Target, g2curve_target::G2Target};
+
+    type F = GoldilocksField;
+    type C = PoseidonGoldilocksConfig;
+    const D: usize = 2;
+
+    fn check_verify_partial(pubkey_share: G2Affine, message: G1Affine, partial_sig: G1Affine) {
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let pubkey_share_t = G2Target::empty(&mut builder);
+        let message_t = G1Target::empty(&mut builder);
+        let partial_sig_t = G1Target::empty(&mut builder);
+        verify_partial(&mut builder, &pubkey_share_t, &message_t, &partial_sig_t);
+
+        let mut pw = PartialWitness::new();
+        pubkey_share_t.set_witness(&mut pw, &pubkey_share);
+        message_t.set_witness(&mut pw, &message);
+        partial_sig_t.set_witness(&mut pw, &partial_sig);
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_verify_partial() {
+        let rng = &mut rand::thread_rng();
+        let secret_share = Fr::rand(rng);
+        let pubkey_share: G2Affine = (G2Affine::generator() * secret_share).into_affine();
+        let message = G1Affine::rand(rng);
+        let partial_sig: G1Affine = (message * secret_share).into_affine();
+        check_verify_partial(pubkey_share, message, partial_sig);
+    }
+
+    // e(O, g2) = e(m, O) = 1, the zero share key would accept the identity signature
+    #[test]
+    #[should_panic]
+    fn test_verify_partial_rejects_identity_key() {
+        let message = G1Affine::rand(&mut rand::thread_rng());
+        check_verify_partial(G2Affine::identity(), message, G1Affine::identity());
+    }
+
+    fn check_verify_distinct(entries: &[(G2Affine, G1Affine, G1Affine)]) {
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let entries_t: Vec<_> = entries
+            .iter()
+            .map(|_| {
+                (
+                    G2Target::empty(&mut builder),
+                    G1Target::empty(&mut builder),
+                    G1Target::empty(&mut builder),
+                )
+            })
+            .collect();
+        verify_distinct(&mut builder, &entries_t);
+
+        let mut pw = PartialWitness::new();
+        for ((pubkey_t, message_t, sig_t), (pubkey, message, sig)) in
+            entries_t.iter().zip(entries.iter())
+        {
+            pubkey_t.set_witness(&mut pw, pubkey);
+            message_t.set_witness(&mut pw, message);
+            sig_t.set_witness(&mut pw, sig);
+        }
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    fn random_entry() -> (G2Affine, G1Affine, G1Affine) {
+        let rng = &mut rand::thread_rng();
+        let secret = Fr::rand(rng);
+        let pubkey: G2Affine = (G2Affine::generator() * secret).into_affine();
+        let message = G1Affine::rand(rng);
+        let sig: G1Affine = (message * secret).into_affine();
+        (pubkey, message, sig)
+    }
+
+    #[test]
+    fn test_verify_distinct() {
+        let entries = (0..3).map(|_| random_entry()).collect::<Vec<_>>();
+        check_verify_distinct(&entries);
+    }
+
+    // an entry with the identity as key and signature would otherwise drop out of the product
+    #[test]
+    #[should_panic]
+    fn test_verify_distinct_rejects_identity_key() {
+        let message = G1Affine::rand(&mut rand::thread_rng());
+        let entries = [
+            random_entry(),
+            (G2Affine::identity(), message, G1Affine::identity()),
+        ];
+        check_verify_distinct(&entries);
+    }
+}
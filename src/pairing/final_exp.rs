@@ -0,0 +1,311 @@
+use ark_bn254::Config;
+use ark_ec::bn::BnConfig;
+use num_bigint::BigUint;
+use plonky2::{
+    field::extension::Extendable, hash::hash_types::RichField,
+    plonk::circuit_builder::CircuitBuilder,
+};
+
+use crate::fields::{fq12_target::Fq12Target, fr_target::FrTarget};
+
+impl<F: RichField + Extendable<D>, const D: usize> Fq12Target<F, D> {
+    // x -> x^X for x in the cyclotomic subgroup, where X is the BN parameter. X is a
+    // constant, so its bits select plain muls at build time: no conditional_mul needed
+    pub fn pow_by_x(&self, builder: &mut CircuitBuilder<F, D>) -> Self {
+        let x = BigUint::from_slice(
+            &Config::X
+                .iter()
+                .flat_map(|limb| [*limb as u32, (*limb >> 32) as u32])
+                .collect::<Vec<_>>(),
+        );
+        let mut res = self.clone();
+        for i in (0..x.bits() - 1).rev() {
+            res = res.cyclotomic_square(builder);
+            if x.bit(i) {
+                res = res.mul(builder, self);
+            }
+        }
+        if Config::X_IS_NEGATIVE {
+            res = res.cyclotomic_inverse(builder);
+        }
+        res
+    }
+
+    // self^scalar for self in the cyclotomic subgroup, e.g. a pairing output:
+    // cyclotomic_square is wrong anywhere else. MSB-first over the limb bits of scalar, so
+    // an unreduced scalar s + r gives the same result on GT, which has order r
+    pub fn gt_scalar_exp(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        scalar: &FrTarget<F, D>,
+    ) -> Self {
+        let bits = scalar.to_bits(builder);
+        let mut res = Self::one(builder);
+        for bit in bits.iter().rev() {
+            res = res.cyclotomic_square(builder);
+            res = res.conditional_mul(builder, self, bit);
+        }
+        res
+    }
+
+    // x -> x^(-X) for x in the cyclotomic subgroup
+    fn exp_by_neg_x(&self, builder: &mut CircuitBuilder<F, D>) -> Self {
+        self.pow_by_x(builder).cyclotomic_inverse(builder)
+    }
+
+    // f^((p^6 - 1)(p^2 + 1)) = (conj(f) / f)^(p^2 + 1), which lands in the cyclotomic
+    // subgroup. The inverse already rejects f = 0, with a labelled witness generation failure
+    pub fn easy_part_exponentiation(&self, builder: &mut CircuitBuilder<F, D>) -> Self {
+        let f1 = self.conjugate(builder);
+        let f2 = self.inv_labelled(builder, "final exponentiation of zero");
+        let r = f1.mul(builder, &f2);
+        let r_frob2 = r.frobenius_map(builder, 2);
+        r_frob2.mul(builder, &r)
+    }
+
+    // same addition chain as arkworks' Bn::final_exponentiation. A zero Miller loop output
+    // (degenerate input) fails with "final exponentiation of zero"
+    pub fn final_exponentiation(&self, builder: &mut CircuitBuilder<F, D>) -> Self {
+        let r = self.easy_part_exponentiation(builder);
+
+        // hard part, r is in the cyclotomic subgroup from here on
+        let y0 = r.exp_by_neg_x(builder);
+        let y1 = y0.cyclotomic_square(builder);
+        let y2 = y1.cyclotomic_square(builder);
+        let y3 = y2.mul(builder, &y1);
+        let y4 = y3.exp_by_neg_x(builder);
+        let y5 = y4.cyclotomic_square(builder);
+        let y6 = y5.exp_by_neg_x(builder);
+        let y3 = y3.cyclotomic_inverse(builder);
+        let y6 = y6.cyclotomic_inverse(builder);
+        let y7 = y6.mul(builder, &y4);
+        let y8 = y7.mul(builder, &y3);
+        let y9 = y8.mul(builder, &y1);
+        let y10 = y8.mul(builder, &y4);
+        let y11 = y10.mul(builder, &r);
+        let y12 = y9.frobenius_map(builder, 1);
+        let y13 = y12.mul(builder, &y11);
+        let y8 = y8.frobenius_map(builder, 2);
+        let y14 = y8.mul(builder, &y13);
+        let r = r.cyclotomic_inverse(builder);
+        let y15 = r.mul(builder, &y9);
+        let y15 = y15.frobenius_map(builder, 3);
+        y15.mul(builder, &y14)
+    }
+
+    // asserts self lies in GT, the order r subgroup, without exponentiating by r (Scott,
+    // "Efficient subgroup membership testing for pairing groups"). First self^(p^4 - p^2 + 1)
+    // = 1, i.e. self^(p^4) * self = self^(p^2) for nonzero self, puts self in the cyclotomic
+    // subgroup. There p = 6X^2 mod r, and self^p = self^(6X^2) holds exactly on GT
+    pub fn assert_in_gt_subgroup(&self, builder: &mut CircuitBuilder<F, D>) {
+        self.assert_nonzero(builder);
+        let frob2 = self.frobenius_map(builder, 2);
+        let frob4 = self.frobenius_map(builder, 4);
+        let frob4_mul_self = frob4.mul(builder, self);
+        Self::connect(builder, &frob4_mul_self, &frob2);
+
+        // (self^(-X))^(-X) = self^(X^2), then ^6 with cyclotomic squarings
+        let y = self.exp_by_neg_x(builder).exp_by_neg_x(builder);
+        let y2 = y.cyclotomic_square(builder);
+        let y4 = y2.cyclotomic_square(builder);
+        let y6 = y4.mul(builder, &y2);
+        let frob1 = self.frobenius_map(builder, 1);
+        Self::connect(builder, &frob1, &y6);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bn254::{Bn254, Config, Fq12, Fr, G1Affine, G2Affine};
+    use ark_ec::{
+        bn::BnConfig,
+        pairing::{MillerLoopOutput, Pairing},
+    };
+    use ark_ff::{CyclotomicMultSubgroup, Field};
+    use ark_std::{One, UniformRand, Zero};
+    use plonky2::{
+        field::goldilocks_field::GoldilocksField,
+        iop::witness::PartialWitness,
+        plonk::{
+            circuit_builder::CircuitBuilder, circuit_data::CircuitConfig,
+            config::PoseidonGoldilocksConfig,
+        },
+    };
+
+    use crate::{
+        curves::{g1curve_target::G1Target, g2curve_target::G2Target},
+        fields::{fq12_target::Fq12Target, fr_target::FrTarget},
+        pairing::miller_loop,
+    };
+
+    type F = GoldilocksField;
+    type C = PoseidonGoldilocksConfig;
+    const D: usize = 2;
+
+    #[test]
+    fn test_pairing() {
+        let rng = &mut rand::thread_rng();
+        let p = G1Affine::rand(rng);
+        let q = G2Affine::rand(rng);
+        let expected: Fq12 = Bn254::pairing(p, q).0;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let p_t = G1Target::constant(&mut builder, p);
+        let q_t = G2Target::constant(&mut builder, q);
+        let f_t = miller_loop(&mut builder, &p_t, &q_t);
+        let output_t = f_t.final_exponentiation(&mut builder);
+        let expected_t = Fq12Target::constant(&mut builder, expected);
+        Fq12Target::connect(&mut builder, &output_t, &expected_t);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw);
+    }
+
+    #[test]
+    fn test_final_exponentiation() {
+        let rng = &mut rand::thread_rng();
+        let f = Fq12::rand(rng);
+        let expected = Bn254::final_exponentiation(MillerLoopOutput(f)).unwrap().0;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let f_t = Fq12Target::empty(&mut builder);
+        let output_t = f_t.final_exponentiation(&mut builder);
+        let expected_t = Fq12Target::constant(&mut builder, expected);
+        Fq12Target::connect(&mut builder, &output_t, &expected_t);
+
+        let mut pw = PartialWitness::new();
+        f_t.set_witness(&mut pw, &f);
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "final exponentiation of zero")]
+    fn test_final_exponentiation_rejects_zero() {
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let f_t = Fq12Target::empty(&mut builder);
+        let _output_t = f_t.final_exponentiation(&mut builder);
+
+        let mut pw = PartialWitness::new();
+        f_t.set_witness(&mut pw, &Fq12::zero());
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_easy_part_exponentiation() {
+        let rng = &mut rand::thread_rng();
+        let f = Fq12::rand(rng);
+        let mut f_conj = f;
+        f_conj.conjugate_in_place();
+        let r = f_conj / f;
+        let expected = r.frobenius_map(2) * r;
+        // cyclotomic elements are unitary: x * conj(x) = 1
+        let mut expected_conj = expected;
+        expected_conj.conjugate_in_place();
+        assert!((expected * expected_conj).is_one());
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let f_t = Fq12Target::empty(&mut builder);
+        let output_t = f_t.easy_part_exponentiation(&mut builder);
+        let expected_t = Fq12Target::constant(&mut builder, expected);
+        Fq12Target::connect(&mut builder, &output_t, &expected_t);
+
+        let mut pw = PartialWitness::new();
+        f_t.set_witness(&mut pw, &f);
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    fn check_assert_in_gt_subgroup(x: Fq12) {
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::empty(&mut builder);
+        x_t.assert_in_gt_subgroup(&mut builder);
+
+        let mut pw = PartialWitness::new();
+        x_t.set_witness(&mut pw, &x);
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_assert_in_gt_subgroup() {
+        let rng = &mut rand::thread_rng();
+        let x = Bn254::pairing(G1Affine::rand(rng), G2Affine::rand(rng)).0;
+        check_assert_in_gt_subgroup(x);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_assert_in_gt_subgroup_rejects_random() {
+        let rng = &mut rand::thread_rng();
+        check_assert_in_gt_subgroup(Fq12::rand(rng));
+    }
+
+    // r^((p^6 - 1)(p^2 + 1)) is in the cyclotomic subgroup but almost never in GT
+    #[test]
+    #[should_panic]
+    fn test_assert_in_gt_subgroup_rejects_cyclotomic() {
+        let rng = &mut rand::thread_rng();
+        let r = Fq12::rand(rng);
+        let mut x = r;
+        x.conjugate_in_place();
+        let x = x / r;
+        let x = x.frobenius_map(2) * x;
+        check_assert_in_gt_subgroup(x);
+    }
+
+    #[test]
+    fn test_gt_scalar_exp() {
+        let rng = &mut rand::thread_rng();
+        let x = Bn254::pairing(G1Affine::rand(rng), G2Affine::rand(rng));
+        let s = Fr::rand(rng);
+        let expected = (x * s).0;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::empty(&mut builder);
+        let s_t = FrTarget::empty(&mut builder);
+        let output_t = x_t.gt_scalar_exp(&mut builder, &s_t);
+        let expected_t = Fq12Target::constant(&mut builder, expected);
+        Fq12Target::connect(&mut builder, &output_t, &expected_t);
+
+        let mut pw = PartialWitness::new();
+        x_t.set_witness(&mut pw, &x.0);
+        s_t.set_witness(&mut pw, &s);
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_pow_by_x() {
+        let rng = &mut rand::thread_rng();
+        let r = Fq12::rand(rng);
+        let mut x = r;
+        x.conjugate_in_place();
+        let x = x / r;
+        let x = x.frobenius_map(2) * x;
+        let mut expected = x.cyclotomic_exp(Config::X);
+        if Config::X_IS_NEGATIVE {
+            expected = expected.cyclotomic_inverse().unwrap();
+        }
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = Fq12Target::empty(&mut builder);
+        let output_t = x_t.pow_by_x(&mut builder);
+        let expected_t = Fq12Target::constant(&mut builder, expected);
+        Fq12Target::connect(&mut builder, &output_t, &expected_t);
+
+        let mut pw = PartialWitness::new();
+        x_t.set_witness(&mut pw, &x);
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+}
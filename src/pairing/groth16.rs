@@ -0,0 +1,215 @@
+use ark_bn254::{Bn254, G1Affine};
+use ark_ec::pairing::Pairing;
+use ark_groth16::VerifyingKey;
+use itertools::Itertools;
+use plonky2::{
+    field::extension::Extendable, hash::hash_types::RichField,
+    plonk::circuit_builder::CircuitBuilder,
+};
+
+use crate::{
+    curves::{g1curve_target::G1Target, g2curve_target::G2Target},
+    fields::{fq12_target::Fq12Target, fr_target::FrTarget},
+    pairing::{multi_miller_loop, PreparedG2Target},
+};
+
+// everything the verifier needs from a fixed verifying key, as circuit constants
+#[derive(Clone, Debug)]
+pub struct PreparedVkTarget<F: RichField + Extendable<D>, const D: usize> {
+    pub alpha_beta: Fq12Target<F, D>,
+    pub gamma_neg: PreparedG2Target<F, D>,
+    pub delta_neg: PreparedG2Target<F, D>,
+    pub ic: Vec<G1Target<F, D>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ProofTarget<F: RichField + Extendable<D>, const D: usize> {
+    pub a: G1Target<F, D>,
+    pub b: G2Target<F, D>,
+    pub c: G1Target<F, D>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> ProofTarget<F, D> {
+    pub fn empty(builder: &mut CircuitBuilder<F, D>) -> Self {
+        Self {
+            a: G1Target::empty(builder),
+            b: G2Target::empty(builder),
+            c: G1Target::empty(builder),
+        }
+    }
+}
+
+// e(alpha, beta) is computed natively, gamma and delta are negated and prepared once
+pub fn prepare_vk<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    vk: VerifyingKey<Bn254>,
+) -> PreparedVkTarget<F, D> {
+    let alpha_beta = Bn254::pairing(vk.alpha_g1, vk.beta_g2).0;
+    let gamma_neg = -vk.gamma_g2;
+    let delta_neg = -vk.delta_g2;
+    PreparedVkTarget {
+        alpha_beta: Fq12Target::constant(builder, alpha_beta),
+        gamma_neg: PreparedG2Target::constant(builder, gamma_neg),
+        delta_neg: PreparedG2Target::constant(builder, delta_neg),
+        ic: vk
+            .gamma_abc_g1
+            .iter()
+            .map(|p: &G1Affine| G1Target::constant(builder, *p))
+            .collect_vec(),
+    }
+}
+
+// checks e(A, B) * e(L, -gamma) * e(C, -delta) == e(alpha, beta), where
+// L = ic[0] + sum_i x_i * ic[i + 1]. L is built with msm and add_complete, so any public
+// input may be zero. The proof points come from the witness, so A and C are asserted on
+// G1 and B in G2 here
+pub fn verify<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    pvk: &PreparedVkTarget<F, D>,
+    proof: &ProofTarget<F, D>,
+    public_inputs: &[FrTarget<F, D>],
+) {
+    assert_eq!(public_inputs.len() + 1, pvk.ic.len());
+    proof.a.assert_on_curve(builder);
+    proof.b.assert_in_subgroup(builder);
+    proof.c.assert_on_curve(builder);
+    let scalars = public_inputs
+        .iter()
+        .map(|x| x.to_bits(builder))
+        .collect_vec();
+    let sum = G1Target::msm(builder, &scalars, &pvk.ic[1..]);
+    let l = pvk.ic[0].add_complete(builder, &sum);
+    let b = PreparedG2Target::from_g2(builder, &proof.b);
+    let f = multi_miller_loop(
+        builder,
+        &[
+            (proof.a.clone(), b),
+            (l, pvk.gamma_neg.clone()),
+            (proof.c.clone(), pvk.delta_neg.clone()),
+        ],
+    );
+    let e = f.final_exponentiation(builder);
+    Fq12Target::connect(builder, &e, &pvk.alpha_beta);
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bn254::{Bn254, Fq2, Fr, G1Affine, G2Affine};
+    use ark_ec::{AffineRepr, CurveGroup};
+    use ark_groth16::{prepare_verifying_key, Groth16, Proof, VerifyingKey};
+    use ark_std::{UniformRand, Zero};
+    use plonky2::{
+        field::goldilocks_field::GoldilocksField,
+        iop::witness::PartialWitness,
+        plonk::{
+            circuit_builder::CircuitBuilder, circuit_data::CircuitConfig,
+            config::PoseidonGoldilocksConfig,
+        },
+    };
+
+    use super::{prepare_vk, verify, ProofTarget};
+    use crate::{curves::map_to_g2::map_to_g2_without_cofactor_mul, fields::fr_target::FrTarget};
+
+    type F = GoldilocksField;
+    type C = PoseidonGoldilocksConfig;
+    const D: usize = 2;
+
+    // a verifying key with known discrete logs, so a valid proof can be made directly
+    fn vk_and_proof(public_inputs: &[Fr]) -> (VerifyingKey<Bn254>, Proof<Bn254>) {
+        let rng = &mut rand::thread_rng();
+        let g1 = G1Affine::generator();
+        let g2 = G2Affine::generator();
+        let [a, b, c, d, s, t] = [(); 6].map(|_| Fr::rand(rng));
+        let ic = (0..=public_inputs.len())
+            .map(|_| Fr::rand(rng))
+            .collect::<Vec<_>>();
+        let l = ic[0]
+            + public_inputs
+                .iter()
+                .zip(ic.iter().skip(1))
+                .map(|(x, k)| *x * k)
+                .sum::<Fr>();
+        // s t = a b + l c + r d
+        let r = (s * t - a * b - l * c) / d;
+        let vk = VerifyingKey {
+            alpha_g1: (g1 * a).into_affine(),
+            beta_g2: (g2 * b).into_affine(),
+            gamma_g2: (g2 * c).into_affine(),
+            delta_g2: (g2 * d).into_affine(),
+            gamma_abc_g1: ic.iter().map(|k| (g1 * k).into_affine()).collect(),
+        };
+        let proof = Proof {
+            a: (g1 * s).into_affine(),
+            b: (g2 * t).into_affine(),
+            c: (g1 * r).into_affine(),
+        };
+        (vk, proof)
+    }
+
+    fn verify_in_circuit(vk: VerifyingKey<Bn254>, proof: &Proof<Bn254>, public_inputs: &[Fr]) {
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let pvk = prepare_vk(&mut builder, vk);
+        let proof_t = ProofTarget::empty(&mut builder);
+        let public_inputs_t = public_inputs
+            .iter()
+            .map(|x| FrTarget::constant(&mut builder, *x))
+            .collect::<Vec<_>>();
+        verify(&mut builder, &pvk, &proof_t, &public_inputs_t);
+
+        let mut pw = PartialWitness::new();
+        proof_t.a.set_witness(&mut pw, &proof.a);
+        proof_t.b.set_witness(&mut pw, &proof.b);
+        proof_t.c.set_witness(&mut pw, &proof.c);
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_verify_with_prepared_vk() {
+        let rng = &mut rand::thread_rng();
+        let public_inputs = [Fr::rand(rng), Fr::rand(rng)];
+        let (vk, proof) = vk_and_proof(&public_inputs);
+
+        // the native verifier prepares the key from scratch
+        let native_pvk = prepare_verifying_key(&vk);
+        assert!(Groth16::<Bn254>::verify_proof(&native_pvk, &proof, &public_inputs).unwrap());
+
+        verify_in_circuit(vk, &proof, &public_inputs);
+    }
+
+    #[test]
+    fn test_verify_with_zero_public_input() {
+        let rng = &mut rand::thread_rng();
+        let public_inputs = [Fr::zero(), Fr::rand(rng)];
+        let (vk, proof) = vk_and_proof(&public_inputs);
+        let native_pvk = prepare_verifying_key(&vk);
+        assert!(Groth16::<Bn254>::verify_proof(&native_pvk, &proof, &public_inputs).unwrap());
+
+        verify_in_circuit(vk, &proof, &public_inputs);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_verify_rejects_wrong_public_input() {
+        let rng = &mut rand::thread_rng();
+        let public_inputs = [Fr::rand(rng), Fr::rand(rng)];
+        let (vk, proof) = vk_and_proof(&public_inputs);
+        let wrong_inputs = [public_inputs[0] + Fr::from(1u64), public_inputs[1]];
+
+        verify_in_circuit(vk, &proof, &wrong_inputs);
+    }
+
+    // B on the twist but outside G2, the cofactor not cleared
+    #[test]
+    #[should_panic]
+    fn test_verify_rejects_b_outside_g2() {
+        let rng = &mut rand::thread_rng();
+        let public_inputs = [Fr::rand(rng), Fr::rand(rng)];
+        let (vk, mut proof) = vk_and_proof(&public_inputs);
+        proof.b = map_to_g2_without_cofactor_mul(Fq2::from(3u64));
+        assert!(!proof.b.is_in_correct_subgroup_assuming_on_curve());
+
+        verify_in_circuit(vk, &proof, &public_inputs);
+    }
+}
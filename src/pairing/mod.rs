@@ -0,0 +1,893 @@
+use ark_bn254::{Config, Fq, Fq12, G2Affine, G2Prepared};
+use ark_ec::{bn::BnConfig, short_weierstrass::SWCurveConfig, AffineRepr};
+use ark_ff::Field;
+use itertools::Itertools;
+use plonky2::{
+    field::extension::Extendable,
+    hash::{
+        hash_types::{HashOutTarget, RichField},
+        poseidon::PoseidonHash,
+    },
+    iop::target::BoolTarget,
+    plonk::circuit_builder::CircuitBuilder,
+};
+
+use crate::{
+    curves::{
+        g1curve_target::{G1ProjectiveTarget, G1Target},
+        g2curve_target::G2Target,
+    },
+    fields::{fq12_target::Fq12Target, fq2_target::Fq2Target, fq_target::FqTarget},
+};
+
+pub mod bls;
+mod final_exp;
+pub mod groth16;
+
+// line function coefficients (c0, c1, c2) of one Miller loop step, in the order
+// arkworks' G2Prepared stores them for a D-type twist
+pub type EllCoeffTarget<F, const D: usize> = [Fq2Target<F, D>; 3];
+
+// with infinity set the coefficients are meaningless and the Miller loop skips the pair
+#[derive(Clone, Debug)]
+pub struct PreparedG2Target<F: RichField + Extendable<D>, const D: usize> {
+    pub ell_coeffs: Vec<EllCoeffTarget<F, D>>,
+    pub infinity: BoolTarget,
+}
+
+// G2 point in homogeneous projective coordinates, only used while preparing
+struct G2ProjectiveTarget<F: RichField + Extendable<D>, const D: usize> {
+    x: Fq2Target<F, D>,
+    y: Fq2Target<F, D>,
+    z: Fq2Target<F, D>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> G2ProjectiveTarget<F, D> {
+    // same formulas as arkworks' G2HomProjective::double_in_place
+    fn double_in_place(&mut self, builder: &mut CircuitBuilder<F, D>) -> EllCoeffTarget<F, D> {
+        let two_inv = Fq::from(2).inverse().unwrap();
+        let coeff_b = <ark_bn254::g2::Config as SWCurveConfig>::COEFF_B;
+
+        let a = self
+            .x
+            .mul(builder, &self.y)
+            .mul_scalar_const(builder, &two_inv);
+        let b = self.y.mul(builder, &self.y);
+        let c = self.z.mul(builder, &self.z);
+        let c_double = c.add(builder, &c);
+        let c_triple = c_double.add(builder, &c);
+        let e = c_triple.mul_const(builder, &coeff_b);
+        let e_double = e.add(builder, &e);
+        let f = e_double.add(builder, &e);
+        let g = b.add(builder, &f).mul_scalar_const(builder, &two_inv);
+        let y_plus_z = self.y.add(builder, &self.z);
+        let y_plus_z_sq = y_plus_z.mul(builder, &y_plus_z);
+        let b_plus_c = b.add(builder, &c);
+        let h = y_plus_z_sq.sub(builder, &b_plus_c);
+        let i = e.sub(builder, &b);
+        let j = self.x.mul(builder, &self.x);
+        let e_square = e.mul(builder, &e);
+
+        let b_minus_f = b.sub(builder, &f);
+        self.x = a.mul(builder, &b_minus_f);
+        let g_square = g.mul(builder, &g);
+        let e_square_double = e_square.add(builder, &e_square);
+        let e_square_triple = e_square_double.add(builder, &e_square);
+        self.y = g_square.sub(builder, &e_square_triple);
+        self.z = b.mul(builder, &h);
+
+        let j_double = j.add(builder, &j);
+        let j_triple = j_double.add(builder, &j);
+        [h.neg(builder), j_triple, i]
+    }
+
+    // same formulas as arkworks' G2HomProjective::add_in_place
+    fn add_in_place(
+        &mut self,
+        builder: &mut CircuitBuilder<F, D>,
+        q: &G2Target<F, D>,
+    ) -> EllCoeffTarget<F, D> {
+        let qy_z = q.y.mul(builder, &self.z);
+        let theta = self.y.sub(builder, &qy_z);
+        let qx_z = q.x.mul(builder, &self.z);
+        let lambda = self.x.sub(builder, &qx_z);
+        let c = theta.mul(builder, &theta);
+        let d = lambda.mul(builder, &lambda);
+        let e = lambda.mul(builder, &d);
+        let f = self.z.mul(builder, &c);
+        let g = self.x.mul(builder, &d);
+        let g_double = g.add(builder, &g);
+        let e_plus_f = e.add(builder, &f);
+        let h = e_plus_f.sub(builder, &g_double);
+
+        self.x = lambda.mul(builder, &h);
+        let g_minus_h = g.sub(builder, &h);
+        let theta_g_minus_h = theta.mul(builder, &g_minus_h);
+        let e_y = e.mul(builder, &self.y);
+        self.y = theta_g_minus_h.sub(builder, &e_y);
+        self.z = self.z.mul(builder, &e);
+
+        let theta_qx = theta.mul(builder, &q.x);
+        let lambda_qy = lambda.mul(builder, &q.y);
+        let j = theta_qx.sub(builder, &lambda_qy);
+        [lambda, theta.neg(builder), j]
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> PreparedG2Target<F, D> {
+    // the line coefficients of a constant point are circuit constants. The identity has
+    // none, the generator's stand in for them
+    pub fn constant(builder: &mut CircuitBuilder<F, D>, q: G2Affine) -> Self {
+        let infinity = builder.constant_bool(q.infinity);
+        let q = if q.infinity { G2Affine::generator() } else { q };
+        let prepared = G2Prepared::from(q);
+        let ell_coeffs = prepared
+            .ell_coeffs
+            .iter()
+            .map(|(c0, c1, c2)| {
+                [
+                    Fq2Target::constant(builder, *c0),
+                    Fq2Target::constant(builder, *c1),
+                    Fq2Target::constant(builder, *c2),
+                ]
+            })
+            .collect_vec();
+        Self {
+            ell_coeffs,
+            infinity,
+        }
+    }
+
+    // with q's infinity flag set the coefficients come out of (0, 0) and are never used
+    pub fn from_g2(builder: &mut CircuitBuilder<F, D>, q: &G2Target<F, D>) -> Self {
+        let one = Fq2Target::constant(builder, ark_bn254::Fq2::ONE);
+        let mut r = G2ProjectiveTarget {
+            x: q.x.clone(),
+            y: q.y.clone(),
+            z: one,
+        };
+        let neg_q = q.neg(builder);
+        let mut ell_coeffs = vec![];
+        for bit in Config::ATE_LOOP_COUNT.iter().rev().skip(1).copied() {
+            ell_coeffs.push(r.double_in_place(builder));
+            match bit {
+                1 => ell_coeffs.push(r.add_in_place(builder, q)),
+                -1 => ell_coeffs.push(r.add_in_place(builder, &neg_q)),
+                _ => continue,
+            }
+        }
+        let q1 = q.psi(builder);
+        let q2 = q1.psi(builder);
+        let q2 = q2.neg(builder);
+        // BN254's x is positive, so r.y is not negated here
+        ell_coeffs.push(r.add_in_place(builder, &q1));
+        ell_coeffs.push(r.add_in_place(builder, &q2));
+        Self {
+            ell_coeffs,
+            infinity: q.infinity,
+        }
+    }
+}
+
+// G1 coordinates a line is evaluated at. With z set the point is (x : y : z) and every
+// line value gets scaled by z, an Fq factor that final exponentiation removes. skip is
+// set when the G1 or the G2 point of the pair is the identity: e(O, Q) = e(P, O) = 1, so
+// as in arkworks the pair then contributes nothing
+struct LinePoint<'a, F: RichField + Extendable<D>, const D: usize> {
+    x: &'a FqTarget<F, D>,
+    y: &'a FqTarget<F, D>,
+    z: Option<&'a FqTarget<F, D>>,
+    skip: BoolTarget,
+}
+
+fn ell<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    f: &Fq12Target<F, D>,
+    coeffs: &EllCoeffTarget<F, D>,
+    p: &LinePoint<F, D>,
+) -> Fq12Target<F, D> {
+    let c0 = coeffs[0].mul_scalar(builder, p.y);
+    let c1 = coeffs[1].mul_scalar(builder, p.x);
+    let c2 = match p.z {
+        Some(z) => coeffs[2].mul_scalar(builder, z),
+        None => coeffs[2].clone(),
+    };
+    let next = f.mul_by_034(builder, &c0, &c1, &c2);
+    Fq12Target::select(builder, f, &next, &p.skip)
+}
+
+// number of doubling steps of the Miller loop: one per NAF digit of 6x + 2 below the
+// leading one. Each step squares f (except the first) and adds a line per pair
+pub const fn miller_loop_iterations() -> usize {
+    Config::ATE_LOOP_COUNT.len() - 1
+}
+
+fn multi_miller_loop_lines<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    pairs: &[(LinePoint<F, D>, &PreparedG2Target<F, D>)],
+) -> Fq12Target<F, D> {
+    assert!(!Config::X_IS_NEGATIVE);
+    let ate_loop_count = Config::ATE_LOOP_COUNT;
+    let mut coeffs = pairs.iter().map(|(_, q)| q.ell_coeffs.iter()).collect_vec();
+    let mut f = Fq12Target::constant(builder, Fq12::ONE);
+    for i in (1..ate_loop_count.len()).rev() {
+        if i != ate_loop_count.len() - 1 {
+            f = f.square(builder);
+        }
+        for ((p, _), c) in pairs.iter().zip(coeffs.iter_mut()) {
+            f = ell(builder, &f, c.next().unwrap(), p);
+        }
+        let bit = ate_loop_count[i - 1];
+        if bit == 1 || bit == -1 {
+            for ((p, _), c) in pairs.iter().zip(coeffs.iter_mut()) {
+                f = ell(builder, &f, c.next().unwrap(), p);
+            }
+        }
+    }
+    for _ in 0..2 {
+        for ((p, _), c) in pairs.iter().zip(coeffs.iter_mut()) {
+            f = ell(builder, &f, c.next().unwrap(), p);
+        }
+    }
+    f
+}
+
+// product of the Miller loops of all pairs, matching arkworks' Bn254::multi_miller_loop.
+// A pair with a point at infinity (by its flag) contributes 1
+pub fn multi_miller_loop<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    pairs: &[(G1Target<F, D>, PreparedG2Target<F, D>)],
+) -> Fq12Target<F, D> {
+    let lines = pairs
+        .iter()
+        .map(|(p, q)| {
+            let p = LinePoint {
+                x: &p.x,
+                y: &p.y,
+                z: None,
+                skip: builder.or(p.infinity, q.infinity),
+            };
+            (p, q)
+        })
+        .collect_vec();
+    multi_miller_loop_lines(builder, &lines)
+}
+
+// same as multi_miller_loop on the affine normalizations, up to a factor in Fq that
+// final exponentiation removes: only compare results after final_exponentiation.
+// No inversion is needed. z = 0 is the identity, which contributes 1 like a flagged G2
+// point
+pub fn multi_miller_loop_projective<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    pairs: &[(G1ProjectiveTarget<F, D>, PreparedG2Target<F, D>)],
+) -> Fq12Target<F, D> {
+    let lines = pairs
+        .iter()
+        .map(|(p, q)| {
+            let z_zero = p.z.reduce(builder).is_zero(builder);
+            let p = LinePoint {
+                x: &p.x,
+                y: &p.y,
+                z: Some(&p.z),
+                skip: builder.or(z_zero, q.infinity),
+            };
+            (p, q)
+        })
+        .collect_vec();
+    multi_miller_loop_lines(builder, &lines)
+}
+
+// asserts no two (G1, G2) pairs are equal, so a relation cannot hold trivially because a
+// pair is repeated. Quadratic in the number of pairs
+pub fn assert_pairs_distinct<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    pairs: &[(G1Target<F, D>, G2Target<F, D>)],
+) {
+    for (i, (p_i, q_i)) in pairs.iter().enumerate() {
+        for (p_j, q_j) in pairs.iter().skip(i + 1) {
+            let p_equal = p_i.is_equal(builder, p_j);
+            let q_equal = q_i.is_equal(builder, q_j);
+            let pair_equal = builder.and(p_equal, q_equal);
+            builder.assert_zero(pair_equal.target);
+        }
+    }
+}
+
+// e(p_1, q_1) * ... * e(p_n, q_n) == 1, the check of Ethereum's bn256Pairing precompile.
+// Pairs with a point at infinity contribute 1, as in the precompile. With assert_distinct
+// the pairs must also be pairwise distinct, see assert_pairs_distinct
+pub fn multi_pairing_is_one<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    pairs: &[(G1Target<F, D>, G2Target<F, D>)],
+    assert_distinct: bool,
+) -> BoolTarget {
+    if assert_distinct {
+        assert_pairs_distinct(builder, pairs);
+    }
+    let prepared = pairs
+        .iter()
+        .map(|(p, q)| (p.clone(), PreparedG2Target::from_g2(builder, q)))
+        .collect_vec();
+    let f = multi_miller_loop(builder, &prepared);
+    let e = f.final_exponentiation(builder);
+    let one = Fq12Target::constant(builder, Fq12::ONE);
+    e.is_equal(builder, &one)
+}
+
+// e(a_1, b_1) * ... * e(a_n, b_n) == 1 with one Miller loop product and a single final
+// exponentiation. e(A, B) == e(C, D) is pairing_check(&[(A, B), (-C, D)])
+pub fn pairing_check<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    pairs: &[(G1Target<F, D>, G2Target<F, D>)],
+) -> BoolTarget {
+    multi_pairing_is_one(builder, pairs, false)
+}
+
+pub fn assert_pairing_product_is_one<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    pairs: &[(G1Target<F, D>, G2Target<F, D>)],
+) {
+    let is_one = pairing_check(builder, pairs);
+    builder.assert_one(is_one.target);
+}
+
+// Poseidon hash of p's coordinates, x then y, each as 8 zero padded little-endian u32
+// limbs, then the 4 elements of blinding. Binding, and hiding as long as blinding is
+// uniformly random and kept secret: a fresh blinding per commitment
+pub fn commit_g1<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    p: &G1Target<F, D>,
+    blinding: &HashOutTarget,
+) -> HashOutTarget {
+    let mut inputs = p.x.to_limbs(builder).to_vec();
+    inputs.extend(p.y.to_limbs(builder));
+    inputs.extend(blinding.elements);
+    builder.hash_n_to_hash_no_pad::<PoseidonHash>(inputs)
+}
+
+// checks (p_opening, blinding) opens p_commitment (see commit_g1) and
+// e(p_opening, q) == expected
+pub fn verify_committed_g1<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    p_commitment: &HashOutTarget,
+    p_opening: &G1Target<F, D>,
+    blinding: &HashOutTarget,
+    q: &G2Target<F, D>,
+    expected: &Fq12Target<F, D>,
+) {
+    let commitment = commit_g1(builder, p_opening, blinding);
+    builder.connect_hashes(commitment, *p_commitment);
+    let e = pairing(builder, p_opening, q);
+    Fq12Target::connect(builder, &e, expected);
+}
+
+pub fn miller_loop<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    p: &G1Target<F, D>,
+    q: &G2Target<F, D>,
+) -> Fq12Target<F, D> {
+    let q = PreparedG2Target::from_g2(builder, q);
+    multi_miller_loop(builder, &[(p.clone(), q)])
+}
+
+// Miller loop values of each p against one fixed G2 point. The G2 line coefficients are
+// computed once in prepared and shared by every p; call final_exponentiation on an
+// output to get the pairing
+pub fn pairing_many_g1<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    ps: &[G1Target<F, D>],
+    prepared: &PreparedG2Target<F, D>,
+) -> Vec<Fq12Target<F, D>> {
+    ps.iter()
+        .map(|p| multi_miller_loop(builder, &[(p.clone(), prepared.clone())]))
+        .collect()
+}
+
+// e(p, q), equal to ark_bn254::Bn254::pairing. Neither point is checked: the caller
+// asserts p on the curve and q in G2 (G2Target::assert_in_subgroup) when they come from
+// the witness, otherwise the output is not a pairing value
+pub fn pairing<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    p: &G1Target<F, D>,
+    q: &G2Target<F, D>,
+) -> Fq12Target<F, D> {
+    let f = miller_loop(builder, p, q);
+    f.final_exponentiation(builder)
+}
+
+// e(p, g2) for the G2 generator. Its line coefficients are circuit constants computed at
+// build time, so the only in-circuit work is the G1 side and the Fq12 accumulation
+pub fn pairing_with_generator<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    p: &G1Target<F, D>,
+) -> Fq12Target<F, D> {
+    let q = PreparedG2Target::constant(builder, G2Affine::generator());
+    let f = multi_miller_loop(builder, &[(p.clone(), q)]);
+    f.final_exponentiation(builder)
+}
+
+pub fn pairing_projective<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    p: &G1ProjectiveTarget<F, D>,
+    q: &G2Target<F, D>,
+) -> Fq12Target<F, D> {
+    let q = PreparedG2Target::from_g2(builder, q);
+    let f = multi_miller_loop_projective(builder, &[(p.clone(), q)]);
+    f.final_exponentiation(builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use ark_bn254::{Bn254, Config, Fq, Fq2, Fr, G1Affine, G2Affine};
+    use ark_ec::{bn::BnConfig, pairing::Pairing, AffineRepr, CurveGroup};
+    use ark_ff::PrimeField;
+    use ark_std::UniformRand;
+    use num_bigint::BigUint;
+    use plonky2::{
+        field::{
+            goldilocks_field::GoldilocksField,
+            types::{Field, Sample},
+        },
+        hash::{hash_types::HashOut, poseidon::PoseidonHash},
+        iop::witness::{PartialWitness, WitnessWrite},
+        plonk::{
+            circuit_builder::CircuitBuilder,
+            circuit_data::{CircuitConfig, CircuitData},
+            config::{Hasher, PoseidonGoldilocksConfig},
+        },
+    };
+
+    use super::{
+        assert_pairing_product_is_one, miller_loop, miller_loop_iterations, multi_miller_loop,
+        multi_pairing_is_one, pairing, pairing_check, pairing_many_g1, pairing_projective,
+        pairing_with_generator, verify_committed_g1, PreparedG2Target,
+    };
+    use crate::{
+        curves::{
+            g1curve_target::{G1ProjectiveTarget, G1Target},
+            g2curve_target::G2Target,
+            BN254GateSerializer, BN254GeneratorSerializer,
+        },
+        fields::{fq12_target::Fq12Target, fq_target::FqTarget},
+        utils::prove_and_verify,
+    };
+
+    type F = GoldilocksField;
+    type C = PoseidonGoldilocksConfig;
+    const D: usize = 2;
+
+    // go-ethereum's bn256Pairing vector "two_point_match_2": e(g1, g2) * e(-g1, g2) = 1
+    const TWO_POINT_MATCH: &str = "\
+        0000000000000000000000000000000000000000000000000000000000000001\
+        0000000000000000000000000000000000000000000000000000000000000002\
+        198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2\
+        1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed\
+        090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b\
+        12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa\
+        0000000000000000000000000000000000000000000000000000000000000001\
+        30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd45\
+        198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2\
+        1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed\
+        090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b\
+        12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa";
+
+    // e(g1, g2) * e(g1, g2) = e(g1, g2)^2 != 1, the precompile returns 0
+    const TWO_POINT_FAIL: &str = "\
+        0000000000000000000000000000000000000000000000000000000000000001\
+        0000000000000000000000000000000000000000000000000000000000000002\
+        198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2\
+        1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed\
+        090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b\
+        12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa\
+        0000000000000000000000000000000000000000000000000000000000000001\
+        0000000000000000000000000000000000000000000000000000000000000002\
+        198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2\
+        1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed\
+        090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b\
+        12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa";
+
+    // EIP-197 encoding: 192 bytes per pair, 32-byte big-endian words
+    // (x, y) for G1, then (x_im, x_re, y_im, y_re) for G2
+    fn decode_evm_pairing_input(input: &str) -> Vec<(G1Affine, G2Affine)> {
+        let bytes = hex::decode(input).unwrap();
+        assert_eq!(bytes.len() % 192, 0);
+        bytes
+            .chunks(192)
+            .map(|chunk| {
+                let w = chunk
+                    .chunks(32)
+                    .map(Fq::from_be_bytes_mod_order)
+                    .collect::<Vec<_>>();
+                let p = G1Affine::new(w[0], w[1]);
+                let q = G2Affine::new(Fq2::new(w[3], w[2]), Fq2::new(w[5], w[4]));
+                (p, q)
+            })
+            .collect()
+    }
+
+    fn check_evm_vector(input: &str, expected: bool) {
+        let pairs = decode_evm_pairing_input(input);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let pairs_t = pairs
+            .iter()
+            .map(|(p, q)| {
+                (
+                    G1Target::constant(&mut builder, *p),
+                    G2Target::constant(&mut builder, *q),
+                )
+            })
+            .collect::<Vec<_>>();
+        let is_one = multi_pairing_is_one(&mut builder, &pairs_t, false);
+        let expected_t = builder.constant_bool(expected);
+        builder.connect(is_one.target, expected_t.target);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_evm_encoding_of_generators() {
+        let pairs = decode_evm_pairing_input(TWO_POINT_MATCH);
+        assert_eq!(pairs[0].0, G1Affine::generator());
+        assert_eq!(pairs[0].1, G2Affine::generator());
+        assert_eq!(pairs[1].0, -G1Affine::generator());
+    }
+
+    #[test]
+    fn test_multi_pairing_is_one_evm_match() {
+        check_evm_vector(TWO_POINT_MATCH, true);
+    }
+
+    #[test]
+    fn test_multi_pairing_is_one_evm_fail() {
+        check_evm_vector(TWO_POINT_FAIL, false);
+    }
+
+    fn check_duplicate_pairs(assert_distinct: bool) {
+        let rng = &mut rand::thread_rng();
+        let p = G1Affine::rand(rng);
+        let q = G2Affine::rand(rng);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let pair_t = (
+            G1Target::constant(&mut builder, p),
+            G2Target::constant(&mut builder, q),
+        );
+        let pairs_t = [pair_t.clone(), pair_t];
+        multi_pairing_is_one(&mut builder, &pairs_t, assert_distinct);
+
+        prove_and_verify(builder, PartialWitness::new()).unwrap();
+    }
+
+    #[test]
+    fn test_multi_pairing_is_one_duplicate_pairs() {
+        check_duplicate_pairs(false);
+    }
+
+    // the distinctness check is an assert_zero, so a duplicate is a witness conflict
+    #[test]
+    #[should_panic]
+    fn test_multi_pairing_is_one_rejects_duplicate_pairs() {
+        check_duplicate_pairs(true);
+    }
+
+    #[test]
+    fn test_miller_loop() {
+        let rng = &mut rand::thread_rng();
+        let p = G1Affine::rand(rng);
+        let q = G2Affine::rand(rng);
+        let expected = Bn254::miller_loop(p, q).0;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let p_t = G1Target::empty(&mut builder);
+        let q_t = G2Target::empty(&mut builder);
+        let f_t = miller_loop(&mut builder, &p_t, &q_t);
+        let expected_t = Fq12Target::constant(&mut builder, expected);
+        Fq12Target::connect(&mut builder, &f_t, &expected_t);
+
+        let mut pw = PartialWitness::new();
+        p_t.set_witness(&mut pw, &p);
+        q_t.set_witness(&mut pw, &q);
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_pairing_circuit_serialization() {
+        let rng = &mut rand::thread_rng();
+        let p = G1Affine::rand(rng);
+        let q = G2Affine::rand(rng);
+        let expected = Bn254::pairing(p, q).0;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let p_t = G1Target::empty(&mut builder);
+        let q_t = G2Target::empty(&mut builder);
+        let f_t = miller_loop(&mut builder, &p_t, &q_t);
+        let output_t = f_t.final_exponentiation(&mut builder);
+        let expected_t = Fq12Target::constant(&mut builder, expected);
+        Fq12Target::connect(&mut builder, &output_t, &expected_t);
+        let data = builder.build::<C>();
+
+        let gate_serializer = BN254GateSerializer;
+        let generator_serializer = BN254GeneratorSerializer::<C, D> {
+            _phantom: PhantomData,
+        };
+        let bytes = data
+            .to_bytes(&gate_serializer, &generator_serializer)
+            .unwrap();
+        let data =
+            CircuitData::<F, C, D>::from_bytes(&bytes, &gate_serializer, &generator_serializer)
+                .unwrap();
+
+        let mut pw = PartialWitness::new();
+        p_t.set_witness(&mut pw, &p);
+        q_t.set_witness(&mut pw, &q);
+        let proof = data.prove(pw).unwrap();
+        data.verify(proof).unwrap();
+    }
+
+    #[test]
+    fn test_miller_loop_iterations() {
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let q = PreparedG2Target::constant(&mut builder, G2Affine::generator());
+        // one line per doubling, one per nonzero digit below the leading one, two final ones
+        let digits = &Config::ATE_LOOP_COUNT[..Config::ATE_LOOP_COUNT.len() - 1];
+        let additions = digits.iter().filter(|d| **d != 0).count() + 2;
+        assert_eq!(q.ell_coeffs.len() - additions, miller_loop_iterations());
+        // the digits are a signed binary expansion of 6x + 2
+        let value = Config::ATE_LOOP_COUNT
+            .iter()
+            .rev()
+            .fold(0i128, |acc, d| 2 * acc + *d as i128);
+        assert_eq!(value, 6 * Config::X[0] as i128 + 2);
+    }
+
+    #[test]
+    fn test_pairing_projective() {
+        let rng = &mut rand::thread_rng();
+        let p = G1Affine::rand(rng);
+        let q = G2Affine::rand(rng);
+        let z = Fq::rand(rng);
+        let expected = Bn254::pairing(p, q).0;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x_t = FqTarget::constant(&mut builder, p.x * z);
+        let y_t = FqTarget::constant(&mut builder, p.y * z);
+        let z_t = FqTarget::constant(&mut builder, z);
+        let p_t = G1ProjectiveTarget::new(x_t, y_t, z_t);
+        let q_t = G2Target::constant(&mut builder, q);
+
+        let affine_t = p_t.to_affine(&mut builder);
+        let p_affine_t = G1Target::constant(&mut builder, p);
+        G1Target::connect(&mut builder, &affine_t, &p_affine_t);
+
+        let output_t = pairing_projective(&mut builder, &p_t, &q_t);
+        let expected_t = Fq12Target::constant(&mut builder, expected);
+        Fq12Target::connect(&mut builder, &output_t, &expected_t);
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_pairing_many_g1() {
+        let rng = &mut rand::thread_rng();
+        let ps = [G1Affine::rand(rng), G1Affine::rand(rng), G1Affine::rand(rng)];
+        let q = G2Affine::rand(rng);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let ps_t = ps
+            .iter()
+            .map(|p| G1Target::constant(&mut builder, *p))
+            .collect::<Vec<_>>();
+        let q_t = G2Target::constant(&mut builder, q);
+        let prepared = PreparedG2Target::from_g2(&mut builder, &q_t);
+        let outputs_t = pairing_many_g1(&mut builder, &ps_t, &prepared);
+        assert_eq!(outputs_t.len(), ps.len());
+        for (p_t, output_t) in ps_t.iter().zip(outputs_t.iter()) {
+            let expected_t = miller_loop(&mut builder, p_t, &q_t);
+            Fq12Target::connect(&mut builder, output_t, &expected_t);
+        }
+
+        let pw = PartialWitness::new();
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    // e([a] g1, [b] g2) = e(g1, g2)^(ab), with the power taken in-circuit
+    #[test]
+    fn test_pairing_bilinearity() {
+        let rng = &mut rand::thread_rng();
+        let a = Fr::rand(rng);
+        let b = Fr::rand(rng);
+        let g1 = G1Affine::generator();
+        let g2 = G2Affine::generator();
+        let p: G1Affine = (g1 * a).into_affine();
+        let q: G2Affine = (g2 * b).into_affine();
+        let ab: BigUint = (a * b).into_bigint().into();
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let p_t = G1Target::empty(&mut builder);
+        let q_t = G2Target::empty(&mut builder);
+        let e_pq_t = pairing(&mut builder, &p_t, &q_t);
+        let g1_t = G1Target::constant(&mut builder, g1);
+        let g2_t = G2Target::constant(&mut builder, g2);
+        let e_t = pairing(&mut builder, &g1_t, &g2_t);
+        let e_ab_t = e_t.pow_constant(&mut builder, &ab);
+        Fq12Target::connect(&mut builder, &e_pq_t, &e_ab_t);
+        let expected_t = Fq12Target::constant(&mut builder, Bn254::pairing(p, q).0);
+        Fq12Target::connect(&mut builder, &e_pq_t, &expected_t);
+
+        let mut pw = PartialWitness::new();
+        p_t.set_witness(&mut pw, &p);
+        q_t.set_witness(&mut pw, &q);
+        prove_and_verify(builder, pw).unwrap();
+    }
+
+    #[test]
+    fn test_pairing_with_generator() {
+        let rng = &mut rand::thread_rng();
+        let p = G1Affine::rand(rng);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let p_t = G1Target::empty(&mut builder);
+        let output_t = pairing_with_generator(&mut builder, &p_t);
+        let g2_t = G2Target::constant(&mut builder, G2Affine::generator());
+        let expected_t = pairing(&mut builder, &p_t, &g2_t);
+        Fq12Target::connect(&mut builder, &output_t, &expected_t);
+
+        let mut pw = PartialWitness::new();
+        p_t.set_witness(&mut pw, &p);
+        let data = builder.build::<C>();
+        let _proof = data.prove(pw).unwrap();
+    }
+
+    // e(O, Q) = e(P, O) = 1 with the identity given by its flag, and a pair holding the
+    // identity drops out of a product
+    #[test]
+    fn test_pairing_with_infinity() {
+        let rng = &mut rand::thread_rng();
+        let p = G1Affine::rand(rng);
+        let q = G2Affine::rand(rng);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let p_t = G1Target::empty(&mut builder);
+        let q_t = G2Target::empty(&mut builder);
+        let g1_infinity_t = G1Target::infinity(&mut builder);
+        let g2_infinity_t = G2Target::empty(&mut builder);
+        let one_t = Fq12Target::one(&mut builder);
+        for (a_t, b_t) in [(&g1_infinity_t, &q_t), (&p_t, &g2_infinity_t)] {
+            let e_t = pairing(&mut builder, a_t, b_t);
+            Fq12Target::connect(&mut builder, &e_t, &one_t);
+        }
+        let neg_p_t = p_t.neg(&mut builder);
+        let pairs = [
+            (p_t.clone(), q_t.clone()),
+            (g1_infinity_t, q_t.clone()),
+            (neg_p_t, q_t),
+            (p_t, g2_infinity_t),
+        ];
+        let is_one = multi_pairing_is_one(&mut builder, &pairs, false);
+        builder.assert_one(is_one.target);
+
+        let mut pw = PartialWitness::new();
+        pairs[0].0.set_witness(&mut pw, &p);
+        pairs[0].1.set_witness(&mut pw, &q);
+        pairs[3].1.set_witness(&mut pw, &G2Affine::identity());
+        prove_and_verify(builder, pw).unwrap();
+    }
+
+    #[test]
+    fn test_multi_miller_loop_skips_constant_g2_infinity() {
+        let rng = &mut rand::thread_rng();
+        let p = G1Affine::rand(rng);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let p_t = G1Target::constant(&mut builder, p);
+        let q = PreparedG2Target::constant(&mut builder, G2Affine::identity());
+        let f_t = multi_miller_loop(&mut builder, &[(p_t, q)]);
+        let one_t = Fq12Target::one(&mut builder);
+        Fq12Target::connect(&mut builder, &f_t, &one_t);
+
+        prove_and_verify(builder, PartialWitness::new()).unwrap();
+    }
+
+    // BLS signature: sig = [sk] H(m), pk = [sk] g2, valid iff e(sig, g2) == e(H(m), pk)
+    #[test]
+    fn test_pairing_check_bls() {
+        let rng = &mut rand::thread_rng();
+        let sk = Fr::rand(rng);
+        let pk: G2Affine = (G2Affine::generator() * sk).into_affine();
+        let message = G1Affine::rand(rng);
+        let sig: G1Affine = (message * sk).into_affine();
+        let forged: G1Affine = (message * (sk + Fr::from(1u64))).into_affine();
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let g2_t = G2Target::constant(&mut builder, G2Affine::generator());
+        let pk_t = G2Target::constant(&mut builder, pk);
+        let message_t = G1Target::constant(&mut builder, message);
+        let neg_message_t = message_t.neg(&mut builder);
+        for (s, expected) in [(sig, true), (forged, false)] {
+            let s_t = G1Target::constant(&mut builder, s);
+            let pairs = [(s_t, g2_t.clone()), (neg_message_t.clone(), pk_t.clone())];
+            let is_valid = pairing_check(&mut builder, &pairs);
+            let expected_t = builder.constant_bool(expected);
+            builder.connect(is_valid.target, expected_t.target);
+            if expected {
+                assert_pairing_product_is_one(&mut builder, &pairs);
+            }
+        }
+
+        prove_and_verify(builder, PartialWitness::new()).unwrap();
+    }
+
+    // opens a commitment made with blinding using opening_blinding
+    fn check_verify_committed_g1(blinding: HashOut<F>, opening_blinding: HashOut<F>) {
+        let rng = &mut rand::thread_rng();
+        let p = G1Affine::rand(rng);
+        let q = G2Affine::rand(rng);
+        let expected = Bn254::pairing(p, q).0;
+        let mut inputs = [p.x, p.y]
+            .iter()
+            .flat_map(|c| {
+                let c: BigUint = (*c).into();
+                let mut digits = c.to_u32_digits();
+                digits.resize(8, 0);
+                digits
+            })
+            .map(F::from_canonical_u32)
+            .collect::<Vec<_>>();
+        inputs.extend(blinding.elements);
+        let commitment = PoseidonHash::hash_no_pad(&inputs);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let commitment_t = builder.constant_hash(commitment);
+        let p_t = G1Target::empty(&mut builder);
+        let blinding_t = builder.add_virtual_hash();
+        let q_t = G2Target::constant(&mut builder, q);
+        let expected_t = Fq12Target::constant(&mut builder, expected);
+        verify_committed_g1(
+            &mut builder,
+            &commitment_t,
+            &p_t,
+            &blinding_t,
+            &q_t,
+            &expected_t,
+        );
+
+        let mut pw = PartialWitness::new();
+        p_t.set_witness(&mut pw, &p);
+        pw.set_hash_target(blinding_t, opening_blinding);
+        prove_and_verify(builder, pw).unwrap();
+    }
+
+    #[test]
+    fn test_verify_committed_g1() {
+        let blinding = HashOut::rand();
+        check_verify_committed_g1(blinding, blinding);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_verify_committed_g1_rejects_wrong_blinding() {
+        check_verify_committed_g1(HashOut::rand(), HashOut::rand());
+    }
+}
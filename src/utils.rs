@@ -1,24 +1,65 @@
+use alloc::{vec, vec::Vec};
 use bitvec::{order::Lsb0, view::BitView};
 use itertools::Itertools;
-use num::BigUint;
+use num::{BigUint, Zero};
+#[cfg(feature = "std")]
 use plonky2::{
     hash::hash_types::RichField,
     iop::{target::Target, witness::Witness},
 };
+#[cfg(all(test, feature = "std"))]
+use plonky2::{
+    field::goldilocks_field::GoldilocksField,
+    iop::witness::PartialWitness,
+    plonk::{circuit_builder::CircuitBuilder, config::PoseidonGoldilocksConfig},
+};
 
-pub fn biguint_to_bits(x: &BigUint, len: usize) -> Vec<bool> {
+// a value needs more bits than the requested width
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BitError {
+    pub required: usize,
+    pub available: usize,
+}
+
+impl core::fmt::Display for BitError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "value needs {} bits but only {} are available",
+            self.required, self.available
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BitError {}
+
+// little-endian bits of x, zero padded to len
+pub fn try_biguint_to_bits(x: &BigUint, len: usize) -> Result<Vec<bool>, BitError> {
+    let required = x.bits() as usize;
+    if required > len {
+        return Err(BitError {
+            required,
+            available: len,
+        });
+    }
     let limbs = x.to_bytes_le();
     let mut bits = vec![];
     for limb in limbs {
         let limb_bits = limb.view_bits::<Lsb0>().iter().map(|b| *b).collect_vec();
         bits.extend(limb_bits);
     }
-    assert!(bits.len() <= len);
-    let to_padd = vec![false; len - bits.len()];
-    bits.extend(to_padd);
-    bits
+    // drops the zero bits above x in its last byte, or pads up to len
+    bits.resize(len, false);
+    Ok(bits)
+}
+
+pub fn biguint_to_bits(x: &BigUint, len: usize) -> Vec<bool> {
+    try_biguint_to_bits(x, len).unwrap_or_else(|e| panic!("{e}"))
 }
 
+// bits are little-endian: bits[i] is the coefficient of 2^i. A length that is not a
+// multiple of 8 is fine, the missing high bits of the last byte are read as zeros
 pub fn bits_to_biguint(bits: &[bool]) -> BigUint {
     let mut limbs = vec![];
     for chunk in bits.chunks(8) {
@@ -42,11 +83,257 @@ pub fn u32_digits_to_biguint(inputs: &[u32]) -> BigUint {
     bits_to_biguint(&bits)
 }
 
+// a value needs more u32 limbs than the requested array holds
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Overflow {
+    pub required: usize,
+    pub available: usize,
+}
+
+impl core::fmt::Display for Overflow {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "value needs {} u32 limbs but only {} are available",
+            self.required, self.available
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Overflow {}
+
+// limbs are little-endian, as in u32_digits_to_biguint
+pub fn u32_array_to_biguint<const N: usize>(inputs: &[u32; N]) -> BigUint {
+    BigUint::from_slice(inputs)
+}
+
+// little-endian u32 limbs of x, zero padded to N
+pub fn biguint_to_u32_array<const N: usize>(x: &BigUint) -> Result<[u32; N], Overflow> {
+    let digits = x.to_u32_digits();
+    if digits.len() > N {
+        return Err(Overflow {
+            required: digits.len(),
+            available: N,
+        });
+    }
+    let mut limbs = [0u32; N];
+    limbs[..digits.len()].copy_from_slice(&digits);
+    Ok(limbs)
+}
+
+// reads the targets as little-endian limbs of bits_per_limb bits each
+#[cfg(feature = "std")]
+pub fn targets_to_biguint<F: RichField, W: Witness<F>>(
+    pw: &W,
+    x: &[Target],
+    bits_per_limb: usize,
+) -> BigUint {
+    assert!(bits_per_limb > 0 && bits_per_limb <= 64);
+    x.iter().rev().fold(BigUint::zero(), |acc, t| {
+        let limb = pw.get_target(*t).to_canonical_u64();
+        assert!(
+            bits_per_limb == 64 || limb >> bits_per_limb == 0,
+            "limb {} does not fit in {} bits",
+            limb,
+            bits_per_limb
+        );
+        (acc << bits_per_limb) + BigUint::from(limb)
+    })
+}
+
+#[cfg(feature = "std")]
 pub fn get_u256_biguint<F: RichField, W: Witness<F>>(pw: &W, x: &[Target]) -> BigUint {
     assert!(x.len() <= 8);
-    let x_value = x
-        .iter()
-        .map(|x| pw.get_target(*x).to_canonical_u64() as u32)
-        .collect_vec();
-    u32_digits_to_biguint(&x_value)
+    targets_to_biguint(pw, x, 32)
+}
+
+// builds, proves and verifies, so a test fails if the proof does not verify and not only
+// if witness generation fails
+#[cfg(all(test, feature = "std"))]
+pub(crate) fn prove_and_verify(
+    builder: CircuitBuilder<GoldilocksField, 2>,
+    pw: PartialWitness<GoldilocksField>,
+) -> anyhow::Result<()> {
+    let data = builder.build::<PoseidonGoldilocksConfig>();
+    let proof = data.prove(pw)?;
+    data.verify(proof)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use num::BigUint;
+
+    use super::{
+        biguint_to_bits, biguint_to_u32_array, bits_to_biguint, try_biguint_to_bits,
+        u32_array_to_biguint, u32_digits_to_biguint, BitError, Overflow,
+    };
+
+    #[cfg(feature = "std")]
+    type F = plonky2::field::goldilocks_field::GoldilocksField;
+
+    #[cfg(feature = "std")]
+    fn check_targets_to_biguint(num_limbs: usize) {
+        use num::bigint::RandBigInt;
+        use plonky2::{
+            field::{goldilocks_field::GoldilocksField, types::Field},
+            iop::{
+                target::Target,
+                witness::{PartialWitness, WitnessWrite},
+            },
+        };
+
+        use super::targets_to_biguint;
+
+        type F = GoldilocksField;
+
+        let rng = &mut rand::thread_rng();
+        let x = rng.gen_biguint(32 * num_limbs as u64);
+        let mut digits = x.to_u32_digits();
+        digits.resize(num_limbs, 0);
+
+        let mut pw = PartialWitness::<F>::new();
+        let targets = (0..num_limbs)
+            .map(|i| Target::VirtualTarget { index: i })
+            .collect::<Vec<_>>();
+        for (t, d) in targets.iter().zip(digits) {
+            pw.set_target(*t, F::from_canonical_u32(d));
+        }
+        assert_eq!(targets_to_biguint(&pw, &targets, 32), x);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_targets_to_biguint_8_limbs() {
+        check_targets_to_biguint(8);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_targets_to_biguint_12_limbs() {
+        check_targets_to_biguint(12);
+    }
+
+    // a circuit asserting x == 0, with x set to value
+    #[cfg(feature = "std")]
+    fn assert_zero_circuit(
+        value: u64,
+    ) -> (
+        plonky2::plonk::circuit_builder::CircuitBuilder<F, 2>,
+        plonky2::iop::witness::PartialWitness<F>,
+    ) {
+        use plonky2::{
+            field::types::Field,
+            iop::witness::{PartialWitness, WitnessWrite},
+            plonk::{circuit_builder::CircuitBuilder, circuit_data::CircuitConfig},
+        };
+
+        let mut builder = CircuitBuilder::<F, 2>::new(CircuitConfig::standard_ecc_config());
+        let x = builder.add_virtual_target();
+        builder.assert_zero(x);
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(value));
+        (builder, pw)
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_prove_and_verify() {
+        let (builder, pw) = assert_zero_circuit(0);
+        super::prove_and_verify(builder, pw).unwrap();
+    }
+
+    // a violated copy constraint is a witness conflict, which panics during witness
+    // generation rather than surfacing as an Err from prove
+    #[cfg(feature = "std")]
+    #[test]
+    #[should_panic(expected = "set twice with different values")]
+    fn test_prove_and_verify_rejects_broken_constraint() {
+        let (builder, pw) = assert_zero_circuit(1);
+        let _ = super::prove_and_verify(builder, pw);
+    }
+
+    #[test]
+    fn test_try_biguint_to_bits() {
+        let x = BigUint::from(0b1011u32);
+        assert_eq!(try_biguint_to_bits(&x, 4), Ok(vec![true, true, false, true]));
+        assert_eq!(
+            try_biguint_to_bits(&x, 6),
+            Ok(vec![true, true, false, true, false, false])
+        );
+        assert_eq!(
+            try_biguint_to_bits(&x, 3),
+            Err(BitError {
+                required: 4,
+                available: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_bits_to_biguint_unaligned() {
+        // 5 bits: 0b10110
+        let bits = [false, true, true, false, true];
+        assert_eq!(bits_to_biguint(&bits), BigUint::from(0b10110u32));
+        // 13 bits: the top bit lands in bit 4 of the second byte
+        let mut bits = [false; 13];
+        bits[0] = true;
+        bits[12] = true;
+        assert_eq!(bits_to_biguint(&bits), BigUint::from((1u32 << 12) | 1));
+        assert_eq!(biguint_to_bits(&BigUint::from((1u32 << 12) | 1), 13), bits);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_bits_roundtrip_random() {
+        use num::bigint::RandBigInt;
+
+        let rng = &mut rand::thread_rng();
+        for len in [1, 5, 8, 13, 64, 254, 256] {
+            for _ in 0..16 {
+                let x = rng.gen_biguint(len as u64);
+                let bits = biguint_to_bits(&x, len);
+                assert_eq!(bits.len(), len);
+                assert_eq!(bits_to_biguint(&bits), x);
+            }
+        }
+    }
+
+    // runs with and without the `std` feature, i.e. also under `--no-default-features`
+    #[test]
+    fn test_native_bits_roundtrip() {
+        let x = BigUint::from(0xdead_beef_u64) << 100;
+        let bits = biguint_to_bits(&x, 256);
+        assert_eq!(bits.len(), 256);
+        assert_eq!(bits_to_biguint(&bits), x);
+
+        let digits = x.to_u32_digits();
+        assert_eq!(u32_digits_to_biguint(&digits), x);
+    }
+
+    #[test]
+    fn test_u32_array_roundtrip() {
+        let limbs: [u32; 8] = core::array::from_fn(|i| 0x0101_0101 * (i as u32 + 1));
+        let x = u32_array_to_biguint(&limbs);
+        assert_eq!(x, u32_digits_to_biguint(&limbs));
+        assert_eq!(biguint_to_u32_array::<8>(&x), Ok(limbs));
+
+        // small values are zero padded
+        let x = BigUint::from(7u32);
+        assert_eq!(biguint_to_u32_array::<8>(&x), Ok([7, 0, 0, 0, 0, 0, 0, 0]));
+        assert_eq!(biguint_to_u32_array::<8>(&BigUint::from(0u32)), Ok([0; 8]));
+
+        // 2^256 needs a ninth limb
+        let x = BigUint::from(1u32) << 256;
+        assert_eq!(
+            biguint_to_u32_array::<8>(&x),
+            Err(Overflow {
+                required: 9,
+                available: 8
+            })
+        );
+        let max = (BigUint::from(1u32) << 256) - 1u32;
+        assert_eq!(biguint_to_u32_array::<8>(&max), Ok([u32::MAX; 8]));
+    }
 }